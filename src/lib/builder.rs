@@ -0,0 +1,200 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    error::{Result, TreeGenError},
+    filter::{compile_pattern, compile_patterns, DateFilter},
+    render::{Renderer, StreamRenderer},
+    scan::{scan, walk, Node, ScanOptions, SortOrder, TimeField},
+};
+
+/// A scanned directory tree, ready to be rendered in any output format.
+///
+/// ```no_run
+/// use tree_gen::{render::{ascii::AsciiRenderer, RenderOptions}, TreeGen};
+///
+/// let tree = TreeGen::builder("src").depth(2).ignore_hidden(true).build()?;
+/// let ascii = tree.render_to_string(&AsciiRenderer::new(&RenderOptions::default()))?;
+/// # Ok::<(), tree_gen::error::TreeGenError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct TreeGen {
+    root: Node,
+}
+
+impl TreeGen {
+    /// Start configuring a scan of the directory at `path`.
+    pub fn builder(path: impl AsRef<Path>) -> TreeGenBuilder {
+        TreeGenBuilder::new(path)
+    }
+
+    /// The root entry of the scanned tree.
+    pub fn root(&self) -> &Node {
+        &self.root
+    }
+
+    pub fn into_root(self) -> Node {
+        self.root
+    }
+
+    /// Render the tree into `out` with the given renderer.
+    pub fn render(&self, renderer: &dyn Renderer, out: &mut dyn Write) -> Result<()> {
+        renderer.render(&self.root, out)
+    }
+
+    /// Render the tree into a string with the given renderer.
+    pub fn render_to_string(&self, renderer: &dyn Renderer) -> Result<String> {
+        let mut buffer = Vec::new();
+        self.render(renderer, &mut buffer)?;
+        String::from_utf8(buffer).map_err(|err| TreeGenError::Serialize(err.to_string()))
+    }
+}
+
+/// Configures the filters and limits of a scan. Created with [`TreeGen::builder`].
+#[derive(Debug, Clone)]
+pub struct TreeGenBuilder {
+    path: PathBuf,
+    max_depth: Option<usize>,
+    ignore_hidden: bool,
+    file_extension: Option<String>,
+    size_min: Option<u64>,
+    size_max: Option<u64>,
+    date_filter: Option<String>,
+    include: Option<String>,
+    exclude: Vec<String>,
+    time_field: TimeField,
+    sort: SortOrder,
+    directory_sizes: bool,
+}
+
+impl TreeGenBuilder {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        TreeGenBuilder {
+            path: path.as_ref().to_path_buf(),
+            max_depth: None,
+            ignore_hidden: false,
+            file_extension: None,
+            size_min: None,
+            size_max: None,
+            date_filter: None,
+            include: None,
+            exclude: vec![],
+            time_field: TimeField::default(),
+            sort: SortOrder::default(),
+            directory_sizes: true,
+        }
+    }
+
+    /// Maximum depth of the tree. The tree is unlimited by default.
+    pub fn depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Ignore hidden files and folders.
+    pub fn ignore_hidden(mut self, ignore_hidden: bool) -> Self {
+        self.ignore_hidden = ignore_hidden;
+        self
+    }
+
+    /// Only keep files with this extension, and the directories leading to them.
+    pub fn extension(mut self, extension: impl Into<String>) -> Self {
+        self.file_extension = Some(extension.into());
+        self
+    }
+
+    /// Minimum file size in bytes.
+    pub fn size_min(mut self, size_min: u64) -> Self {
+        self.size_min = Some(size_min);
+        self
+    }
+
+    /// Maximum file size in bytes.
+    pub fn size_max(mut self, size_max: u64) -> Self {
+        self.size_max = Some(size_max);
+        self
+    }
+
+    /// Filter files by date, like `after 2024-01-01` or `newer-than 7d`, by the timestamp
+    /// chosen with [`TreeGenBuilder::time_field`]. See [`DateFilter::parse_at`] for every form.
+    pub fn date_filter(mut self, date_filter: impl Into<String>) -> Self {
+        self.date_filter = Some(date_filter.into());
+        self
+    }
+
+    /// Timestamp used by the date filter and the time order, the modification time by default.
+    pub fn time_field(mut self, time_field: TimeField) -> Self {
+        self.time_field = time_field;
+        self
+    }
+
+    /// Order of the entries of each directory, by name by default.
+    pub fn sort(mut self, sort: SortOrder) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// Sum the sizes of the entries listed in each directory into its size, on by default.
+    /// Outputs that show no directory sizes can skip it, see [`Renderer::directory_sizes`].
+    pub fn directory_sizes(mut self, directory_sizes: bool) -> Self {
+        self.directory_sizes = directory_sizes;
+        self
+    }
+
+    /// Only keep files whose name matches this pattern (wildcard or regex).
+    pub fn include(mut self, pattern: impl Into<String>) -> Self {
+        self.include = Some(pattern.into());
+        self
+    }
+
+    /// Drop files and directories whose name matches this pattern (wildcard or regex).
+    /// Can be called more than once to drop entries matching any of the patterns.
+    pub fn exclude(mut self, pattern: impl Into<String>) -> Self {
+        self.exclude.push(pattern.into());
+        self
+    }
+
+    /// Get the scan options, parsing the date filter and compiling the include and exclude patterns.
+    pub fn options(&self) -> Result<ScanOptions> {
+        Ok(ScanOptions {
+            max_depth: self.max_depth,
+            ignore_hidden: self.ignore_hidden,
+            file_extension: self.file_extension.clone(),
+            size_min: self.size_min,
+            size_max: self.size_max,
+            date_filter: self
+                .date_filter
+                .as_deref()
+                .map(DateFilter::parse)
+                .transpose()?,
+            include: self.include.as_deref().map(compile_pattern).transpose()?,
+            exclude: match self.exclude.is_empty() {
+                true => None,
+                false => Some(compile_patterns(&self.exclude)?),
+            },
+            time_field: self.time_field,
+            sort: self.sort,
+            directory_sizes: self.directory_sizes,
+        })
+    }
+
+    /// Scan the directory. Fails on invalid filters or when the path cannot be read,
+    /// unreadable directories inside the tree are reported on their node instead.
+    pub fn build(self) -> Result<TreeGen> {
+        let options = self.options()?;
+        Ok(TreeGen {
+            root: scan(&self.path, &options)?,
+        })
+    }
+
+    /// Walk the directory and render every entry as soon as it is reached, without building
+    /// the tree in memory. Use this for huge trees with formats that support streaming.
+    pub fn stream(self, renderer: &dyn StreamRenderer, out: &mut dyn Write) -> Result<()> {
+        let options = self.options()?;
+        walk(&self.path, &options, &mut |entry| {
+            renderer.render_entry(entry, out)
+        })
+    }
+}
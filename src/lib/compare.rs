@@ -1,114 +1,1194 @@
-use colored::Colorize;
-use std::fs;
-use std::path::Path;
-
-pub fn compare_directories(dir1: &Path, dir2: &Path) {
-    // Helper function for recursive comparison
-    fn compare_recursive(path1: &Path, path2: &Path, indent: &str) {
-        let entries1: Vec<_> = fs::read_dir(path1)
-            .expect("Could not read directory")
-            .filter_map(Result::ok)
-            .collect();
-        let entries2: Vec<_> = fs::read_dir(path2)
-            .expect("Could not read directory")
-            .filter_map(Result::ok)
+use rayon::prelude::*;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use super::{
+    date::{datetime_to_iso8601, unix_timestamp},
+    error::{Result, TreeGenError},
+    parse::parse_tree,
+    render::json::TreeNode,
+    scan::{filter_tree, scan, Node, NodeKind, ScanOptions},
+};
+
+/// What decides that two entries of the same kind differ.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CompareBy {
+    /// Only names and kinds, for trees that do not record anything else like text trees.
+    Structure,
+    #[default]
+    Size,
+    /// Size or modification time, to the second like snapshots record it.
+    Mtime,
+    /// BLAKE3 hash of the contents, for files of the same size.
+    Hash,
+    /// Byte by byte comparison of the contents, for files of the same size.
+    Content,
+}
+
+impl CompareBy {
+    /// Every mode selectable with `--compare-by`.
+    pub const ALL: [CompareBy; 4] = [
+        CompareBy::Size,
+        CompareBy::Mtime,
+        CompareBy::Hash,
+        CompareBy::Content,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CompareBy::Structure => "structure",
+            CompareBy::Size => "size",
+            CompareBy::Mtime => "mtime",
+            CompareBy::Hash => "hash",
+            CompareBy::Content => "content",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<CompareBy> {
+        CompareBy::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+}
+
+/// Metadata of an entry that can be compared besides its contents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Attribute {
+    /// Permission bits, without the file type.
+    Mode,
+    /// Owner user id.
+    Owner,
+    /// Owner group id.
+    Group,
+    /// Modification time, to the second.
+    Mtime,
+}
+
+impl Attribute {
+    /// Every attribute selectable with `--compare-attributes`.
+    pub const ALL: [Attribute; 4] = [
+        Attribute::Mode,
+        Attribute::Owner,
+        Attribute::Group,
+        Attribute::Mtime,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Attribute::Mode => "mode",
+            Attribute::Owner => "owner",
+            Attribute::Group => "group",
+            Attribute::Mtime => "mtime",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Attribute> {
+        Attribute::ALL
+            .into_iter()
+            .find(|attribute| attribute.name() == name)
+    }
+
+    /// Get the attribute of an entry as the outputs show it, like `644` for a mode.
+    /// Trees that do not record it, like text trees, have no value.
+    pub fn value(&self, node: &Node) -> Option<String> {
+        match self {
+            Attribute::Mode => node.mode.map(|mode| format!("{:o}", mode & 0o7777)),
+            Attribute::Owner => node.uid.map(|uid| uid.to_string()),
+            Attribute::Group => node.gid.map(|gid| gid.to_string()),
+            Attribute::Mtime => node.modified.map(datetime_to_iso8601),
+        }
+    }
+}
+
+/// How two trees are compared.
+#[derive(Debug, Clone, Default)]
+pub struct CompareOptions {
+    pub compare_by: CompareBy,
+    /// Pair up removed and added files with the same contents and report them as moved.
+    pub detect_renames: bool,
+    /// Metadata compared on the entries present on both sides, a difference makes an
+    /// entry modified even when its contents are the same.
+    pub attributes: Vec<Attribute>,
+}
+
+/// How an entry changed from the left tree to the right tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Unchanged,
+    Added,
+    Removed,
+    Modified,
+    /// The entry is a file on one side and a directory (or another kind) on the other.
+    TypeChanged,
+    /// The file was renamed or moved from another path, with the same contents.
+    Moved,
+}
+
+impl Change {
+    /// Name of the change used by the structured outputs.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Change::Unchanged => "unchanged",
+            Change::Added => "added",
+            Change::Removed => "removed",
+            Change::Modified => "modified",
+            Change::TypeChanged => "type_changed",
+            Change::Moved => "moved",
+        }
+    }
+}
+
+/// An entry of the comparison of two trees, with the entries it was compared from.
+#[derive(Debug, Clone)]
+pub struct DiffNode {
+    pub name: String,
+    /// Path relative to the compared roots.
+    pub path: PathBuf,
+    pub change: Change,
+    /// The entry in the left tree, without its children. For moved files this is the file
+    /// at its former path.
+    pub left: Option<Node>,
+    /// The entry in the right tree, without its children.
+    pub right: Option<Node>,
+    /// Why the entry could not be read on either side.
+    pub error: Option<String>,
+    /// Whether the contents of a modified entry differ, as decided by the compare mode,
+    /// rather than only its attributes.
+    pub contents_changed: bool,
+    /// Compared attributes that differ between both sides.
+    pub changed_attributes: Vec<Attribute>,
+    /// BLAKE3 hashes of the file on each side, only computed when comparing by hash.
+    pub left_hash: Option<String>,
+    pub right_hash: Option<String>,
+    /// Path relative to the compared roots the file was moved from.
+    pub moved_from: Option<PathBuf>,
+    pub children: Vec<DiffNode>,
+}
+
+/// Number of entries below the compared roots for each kind of change.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct DiffSummary {
+    pub added: usize,
+    pub removed: usize,
+    pub modified: usize,
+    pub type_changed: usize,
+    pub moved: usize,
+    pub unchanged: usize,
+    /// Entries that could not be read on either side.
+    pub errors: usize,
+}
+
+impl DiffSummary {
+    /// Whether the trees differ, unreadable entries aside.
+    pub fn has_differences(&self) -> bool {
+        self.added + self.removed + self.modified + self.type_changed + self.moved > 0
+    }
+
+    fn count(&mut self, diff: &DiffNode) {
+        for child in &diff.children {
+            match child.change {
+                Change::Unchanged => self.unchanged += 1,
+                Change::Added => self.added += 1,
+                Change::Removed => self.removed += 1,
+                Change::Modified => self.modified += 1,
+                Change::TypeChanged => self.type_changed += 1,
+                Change::Moved => self.moved += 1,
+            }
+            if child.error.is_some() {
+                self.errors += 1;
+            }
+            self.count(child);
+        }
+    }
+}
+
+impl fmt::Display for DiffSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} added, {} removed, {} modified, {} type changed, {} moved, {} unchanged",
+            self.added, self.removed, self.modified, self.type_changed, self.moved, self.unchanged
+        )?;
+        if self.errors > 0 {
+            write!(f, ", {} unreadable", self.errors)?;
+        }
+        Ok(())
+    }
+}
+
+impl DiffNode {
+    /// Count the changes of every entry below this one.
+    pub fn summary(&self) -> DiffSummary {
+        let mut summary = DiffSummary::default();
+        if self.error.is_some() {
+            summary.errors += 1;
+        }
+        summary.count(self);
+        summary
+    }
+}
+
+/// Compare two trees entry by entry, matching entries by name. Files of the same size are
+/// read from disk when comparing by hash or content, in parallel.
+pub fn compare_trees(left: &Node, right: &Node, options: &CompareOptions) -> DiffNode {
+    let compare_by = options.compare_by;
+    let mut root = diff_node(PathBuf::new(), Some(left), Some(right), options);
+    root.name = left.name.clone();
+
+    if matches!(compare_by, CompareBy::Hash | CompareBy::Content) {
+        let mut pending = vec![];
+        same_size_files(&mut root, &mut pending);
+        pending
+            .into_par_iter()
+            .for_each(|diff| compare_contents(diff, compare_by));
+    }
+    if options.detect_renames {
+        detect_renames(&mut root);
+    }
+    root
+}
+
+fn diff_node(
+    path: PathBuf,
+    left: Option<&Node>,
+    right: Option<&Node>,
+    options: &CompareOptions,
+) -> DiffNode {
+    let compare_by = options.compare_by;
+    let mut change = match (left, right) {
+        (Some(left), Some(right)) if !same_kind(left, right, compare_by) => Change::TypeChanged,
+        (Some(left), Some(right)) if left.is_dir() || !differs(left, right, compare_by) => {
+            Change::Unchanged
+        }
+        (Some(_), Some(_)) => Change::Modified,
+        (Some(_), None) => Change::Removed,
+        (None, _) => Change::Added,
+    };
+    let contents_changed = change == Change::Modified;
+
+    // Attributes only one side records are not compared, nor those of the compared roots
+    // themselves, like the names of the roots only the entries inside them are compared
+    let mut changed_attributes = vec![];
+    let is_root = path.as_os_str().is_empty();
+    if let (Some(left), Some(right), Change::Unchanged | Change::Modified, false) =
+        (left, right, change, is_root)
+    {
+        changed_attributes = options
+            .attributes
+            .iter()
+            .filter(
+                |attribute| match (attribute.value(left), attribute.value(right)) {
+                    (Some(left), Some(right)) => left != right,
+                    _ => false,
+                },
+            )
+            .copied()
             .collect();
+    }
+    if !changed_attributes.is_empty() {
+        change = Change::Modified;
+    }
 
-        // Sort the entries by file name to make comparison easier
-        let mut entries1 = entries1.iter().map(|e| e.file_name()).collect::<Vec<_>>();
-        let mut entries2 = entries2.iter().map(|e| e.file_name()).collect::<Vec<_>>();
-        entries1.sort();
-        entries2.sort();
-
-        let mut i = 0;
-        let mut j = 0;
-
-        while i < entries1.len() || j < entries2.len() {
-            let entry1 = entries1.get(i);
-            let entry2 = entries2.get(j);
-
-            match (entry1, entry2) {
-                (Some(name1), Some(name2)) => {
-                    match name1.cmp(name2) {
-                        std::cmp::Ordering::Equal => {
-                            // Both directories contain this entry
-                            let path1_item = path1.join(name1);
-                            let path2_item = path2.join(name2);
-
-                            // Check if it is a file or a directory
-                            let is_dir1 = path1_item.is_dir();
-                            let is_dir2 = path2_item.is_dir();
-
-                            if is_dir1 && is_dir2 {
-                                // Recursively compare subdirectories
-                                println!("{}{}", indent, name1.to_string_lossy().cyan());
-                                compare_recursive(
-                                    &path1_item,
-                                    &path2_item,
-                                    &(indent.to_string() + "  "),
-                                );
-                            } else if !is_dir1 && !is_dir2 {
-                                // Compare files by size
-                                let size1 = fs::metadata(&path1_item).unwrap().len();
-                                let size2 = fs::metadata(&path2_item).unwrap().len();
-                                if size1 != size2 {
-                                    println!(
-                                        "{}~ {} ({} bytes in {}, {} bytes in {})",
-                                        indent,
-                                        name1.to_string_lossy().yellow(),
-                                        size1,
-                                        path1.display(),
-                                        size2,
-                                        path2.display()
-                                    );
-                                } else {
-                                    // Files are identical
-                                    println!("{}= {}", indent, name1.to_string_lossy().green());
-                                }
-                            } else {
-                                // One is a file, the other is a directory
-                                println!(
-                                    "{}{} (one is a file, the other is a directory)",
-                                    indent,
-                                    name1.to_string_lossy().red()
-                                );
-                            }
-
-                            i += 1;
-                            j += 1;
-                        }
-                        std::cmp::Ordering::Less => {
-                            // Only in dir1
-                            println!("{}- {}", indent, name1.to_string_lossy().red());
-                            i += 1;
-                        }
-                        std::cmp::Ordering::Greater => {
-                            // Only in dir2
-                            println!("{}+ {}", indent, name2.to_string_lossy().green());
-                            j += 1;
-                        }
-                    }
-                }
-                (Some(name1), None) => {
-                    // Only in dir1
-                    println!("{}- {}", indent, name1.to_string_lossy().red());
-                    i += 1;
-                }
-                (None, Some(name2)) => {
-                    // Only in dir2
-                    println!("{}+ {}", indent, name2.to_string_lossy().green());
-                    j += 1;
-                }
-                (None, None) => break,
+    // Entries only on one side keep their contents, entries that changed type are not descended
+    let children = match change {
+        Change::TypeChanged => vec![],
+        _ => {
+            let mut by_name: BTreeMap<&str, (Option<&Node>, Option<&Node>)> = BTreeMap::new();
+            for child in left.iter().flat_map(|node| &node.children) {
+                by_name.entry(&child.name).or_default().0 = Some(child);
             }
+            for child in right.iter().flat_map(|node| &node.children) {
+                by_name.entry(&child.name).or_default().1 = Some(child);
+            }
+            by_name
+                .into_iter()
+                .map(|(name, (left, right))| diff_node(path.join(name), left, right, options))
+                .collect()
+        }
+    };
+
+    DiffNode {
+        name: left
+            .or(right)
+            .map(|node| node.name.clone())
+            .unwrap_or_default(),
+        path,
+        change,
+        error: left
+            .and_then(|node| node.error.clone())
+            .or_else(|| right.and_then(|node| node.error.clone())),
+        contents_changed,
+        changed_attributes,
+        left: left.map(entry_only),
+        right: right.map(entry_only),
+        left_hash: None,
+        right_hash: None,
+        moved_from: None,
+        children,
+    }
+}
+
+/// Check whether two entries have the same kind. A text tree draws empty directories and
+/// special files just like regular files, so those match files when comparing structure.
+fn same_kind(left: &Node, right: &Node, compare_by: CompareBy) -> bool {
+    let looks_like_file = |node: &Node| node.kind != NodeKind::Symlink && node.children.is_empty();
+    left.kind == right.kind
+        || (compare_by == CompareBy::Structure
+            && (left.is_file() || right.is_file())
+            && looks_like_file(left)
+            && looks_like_file(right))
+}
+
+/// Check whether two entries of the same kind differ.
+fn differs(left: &Node, right: &Node, compare_by: CompareBy) -> bool {
+    if left.link_target != right.link_target {
+        return true;
+    }
+    match compare_by {
+        CompareBy::Structure => false,
+        CompareBy::Mtime => {
+            left.size != right.size
+                || left.modified.map(unix_timestamp) != right.modified.map(unix_timestamp)
+        }
+        // Files of the same size are compared on disk afterwards
+        CompareBy::Size | CompareBy::Hash | CompareBy::Content => left.size != right.size,
+    }
+}
+
+/// Collect the regular files present on both sides that could not be told apart by size.
+fn same_size_files<'a>(diff: &'a mut DiffNode, pending: &mut Vec<&'a mut DiffNode>) {
+    let is_file = |node: &Option<Node>| node.as_ref().is_some_and(Node::is_file);
+    let same_size =
+        matches!(diff.change, Change::Unchanged | Change::Modified) && !diff.contents_changed;
+    if same_size && is_file(&diff.left) && is_file(&diff.right) {
+        pending.push(diff);
+        return;
+    }
+    for child in &mut diff.children {
+        same_size_files(child, pending);
+    }
+}
+
+/// Compare the contents of a file on both sides, marking it modified when they differ.
+/// A file that cannot be read counts as modified, with the reason as its error.
+fn compare_contents(diff: &mut DiffNode, compare_by: CompareBy) {
+    let (Some(left), Some(right)) = (&diff.left, &diff.right) else {
+        return;
+    };
+
+    let differs = match compare_by {
+        CompareBy::Hash => hash_file(&left.path).and_then(|left_hash| {
+            let right_hash = hash_file(&right.path)?;
+            let differs = left_hash != right_hash;
+            diff.left_hash = Some(left_hash);
+            diff.right_hash = Some(right_hash);
+            Ok(differs)
+        }),
+        _ => contents_differ(&left.path, &right.path),
+    };
+
+    match differs {
+        Ok(false) => {}
+        Ok(true) => {
+            diff.change = Change::Modified;
+            diff.contents_changed = true;
+        }
+        Err(err) => {
+            diff.change = Change::Modified;
+            diff.contents_changed = true;
+            diff.error = Some(format!("error reading file: {}", err));
         }
     }
+}
+
+/// A removed or added file that could be one side of a move.
+struct MoveCandidate {
+    path: PathBuf,
+    disk_path: PathBuf,
+    size: u64,
+    hash: Option<String>,
+}
+
+/// Pair up removed and added files with the same hash and turn them into moves. When several
+/// files have the same contents, the one with the most similar name is picked, then the first
+/// by path. Empty files are left alone, they all have the same contents.
+fn detect_renames(root: &mut DiffNode) {
+    let (mut removed, mut added) = (vec![], vec![]);
+    move_candidates(root, &mut removed, &mut added);
+
+    // Only files with a counterpart of the same size need to be hashed
+    let removed_sizes: HashSet<u64> = removed.iter().map(|file| file.size).collect();
+    let added_sizes: HashSet<u64> = added.iter().map(|file| file.size).collect();
+    removed.retain(|file| added_sizes.contains(&file.size));
+    added.retain(|file| removed_sizes.contains(&file.size));
+    removed
+        .par_iter_mut()
+        .chain(added.par_iter_mut())
+        .for_each(|file| file.hash = hash_file(&file.disk_path).ok());
+
+    let mut added_by_hash: HashMap<String, Vec<MoveCandidate>> = HashMap::new();
+    for file in added {
+        if let Some(hash) = file.hash.clone() {
+            added_by_hash.entry(hash).or_default().push(file);
+        }
+    }
+
+    let mut moves = vec![];
+    for from in &removed {
+        let Some(candidates) = from
+            .hash
+            .as_ref()
+            .and_then(|hash| added_by_hash.get_mut(hash))
+        else {
+            continue;
+        };
+        let best = candidates
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, to)| (name_similarity(&from.path, &to.path), Reverse(&to.path)))
+            .map(|(i, _)| i);
+        if let Some(i) = best {
+            let to = candidates.swap_remove(i);
+            moves.push((from.path.clone(), to.path, from.hash.clone()));
+        }
+    }
+
+    for (from, to, hash) in moves {
+        let Some(removed) = take_entry(root, &from) else {
+            continue;
+        };
+        if let Some(added) = find_entry(root, &to) {
+            added.change = Change::Moved;
+            added.left = removed.left;
+            added.left_hash = hash.clone();
+            added.right_hash = hash;
+            added.moved_from = Some(from);
+        }
+    }
+}
+
+fn move_candidates(
+    diff: &DiffNode,
+    removed: &mut Vec<MoveCandidate>,
+    added: &mut Vec<MoveCandidate>,
+) {
+    let candidate = |node: &Option<Node>| {
+        node.as_ref()
+            .filter(|node| node.is_file() && node.size > 0)
+            .map(|node| MoveCandidate {
+                path: diff.path.clone(),
+                disk_path: node.path.clone(),
+                size: node.size,
+                hash: None,
+            })
+    };
+    match diff.change {
+        Change::Removed => removed.extend(candidate(&diff.left)),
+        Change::Added => added.extend(candidate(&diff.right)),
+        _ => {}
+    }
+    for child in &diff.children {
+        move_candidates(child, removed, added);
+    }
+}
+
+/// Score how alike two paths are: the same file name first, then the same extension,
+/// then the length of the common start of the names.
+fn name_similarity(from: &Path, to: &Path) -> (bool, bool, usize) {
+    let (from_name, to_name) = (from.file_name(), to.file_name());
+    let common_prefix = from_name
+        .zip(to_name)
+        .map(|(from_name, to_name)| {
+            let (from_name, to_name) = (from_name.to_string_lossy(), to_name.to_string_lossy());
+            from_name
+                .chars()
+                .zip(to_name.chars())
+                .take_while(|(a, b)| a == b)
+                .count()
+        })
+        .unwrap_or(0);
+    (
+        from_name == to_name,
+        from.extension() == to.extension(),
+        common_prefix,
+    )
+}
+
+/// Find the entry at a path relative to the compared roots.
+fn find_entry<'a>(diff: &'a mut DiffNode, path: &Path) -> Option<&'a mut DiffNode> {
+    let mut current = diff;
+    for component in path.components() {
+        current = current
+            .children
+            .iter_mut()
+            .find(|child| child.name.as_str() == component.as_os_str())?;
+    }
+    Some(current)
+}
+
+/// Remove the entry at a path relative to the compared roots from the comparison.
+fn take_entry(diff: &mut DiffNode, path: &Path) -> Option<DiffNode> {
+    let parent = find_entry(diff, path.parent()?)?;
+    let index = parent
+        .children
+        .iter()
+        .position(|child| child.path == path)?;
+    Some(parent.children.remove(index))
+}
 
-    // Start the comparison
-    println!(
-        "Comparing directories: {} vs {}",
-        dir1.display(),
-        dir2.display()
+/// Get the BLAKE3 hash of a file as a hex string.
+pub fn hash_file(path: &Path) -> io::Result<String> {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update_reader(File::open(path)?)?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Compare two files byte by byte, stopping at the first difference.
+fn contents_differ(left: &Path, right: &Path) -> io::Result<bool> {
+    let (mut left, mut right) = (File::open(left)?, File::open(right)?);
+    let (mut left_buffer, mut right_buffer) = (vec![0; 64 * 1024], vec![0; 64 * 1024]);
+    loop {
+        let read = read_full(&mut left, &mut left_buffer)?;
+        if read_full(&mut right, &mut right_buffer)? != read
+            || left_buffer[..read] != right_buffer[..read]
+        {
+            return Ok(true);
+        }
+        if read == 0 {
+            return Ok(false);
+        }
+    }
+}
+
+/// Fill `buffer` as far as the file allows, so both files are read in the same chunks.
+fn read_full(file: &mut File, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match file.read(&mut buffer[filled..])? {
+            0 => break,
+            read => filled += read,
+        }
+    }
+    Ok(filled)
+}
+
+/// Copy a node without its children, the diff has its own.
+fn entry_only(node: &Node) -> Node {
+    Node {
+        name: node.name.clone(),
+        path: node.path.clone(),
+        kind: node.kind,
+        size: node.size,
+        mode: node.mode,
+        modified: node.modified,
+        accessed: node.accessed,
+        changed: node.changed,
+        created: node.created,
+        uid: node.uid,
+        gid: node.gid,
+        inode: node.inode,
+        nlink: node.nlink,
+        link_target: node.link_target.clone(),
+        error: node.error.clone(),
+        children: vec![],
+    }
+}
+
+/// How an entry changed in a three-way comparison, from the base tree to our and their trees.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeChange {
+    Unchanged,
+    ChangedInOurs,
+    ChangedInTheirs,
+    /// Both sides made the same change.
+    ChangedInBoth,
+    /// Both sides changed the entry differently.
+    Conflict,
+    AddedInOurs,
+    AddedInTheirs,
+    DeletedInOurs,
+    DeletedInTheirs,
+}
+
+impl MergeChange {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MergeChange::Unchanged => "unchanged",
+            MergeChange::ChangedInOurs => "changed_in_ours",
+            MergeChange::ChangedInTheirs => "changed_in_theirs",
+            MergeChange::ChangedInBoth => "changed_in_both",
+            MergeChange::Conflict => "conflict",
+            MergeChange::AddedInOurs => "added_in_ours",
+            MergeChange::AddedInTheirs => "added_in_theirs",
+            MergeChange::DeletedInOurs => "deleted_in_ours",
+            MergeChange::DeletedInTheirs => "deleted_in_theirs",
+        }
+    }
+}
+
+/// An entry of the three-way comparison of a base tree with our and their trees.
+#[derive(Debug, Clone)]
+pub struct MergeNode {
+    pub name: String,
+    /// Path relative to the compared roots.
+    pub path: PathBuf,
+    pub change: MergeChange,
+    /// The entry in each tree, without its children.
+    pub base: Option<Node>,
+    pub ours: Option<Node>,
+    pub theirs: Option<Node>,
+    /// Why the entry could not be read in any of the trees.
+    pub error: Option<String>,
+    pub children: Vec<MergeNode>,
+}
+
+impl MergeNode {
+    /// Count the entries below this one with each kind of change.
+    pub fn summary(&self) -> MergeSummary {
+        let mut summary = MergeSummary::default();
+        summary.count(self);
+        summary
+    }
+}
+
+/// Number of entries below the compared roots for each kind of three-way change.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MergeSummary {
+    pub changed_in_ours: usize,
+    pub changed_in_theirs: usize,
+    pub changed_in_both: usize,
+    pub conflicts: usize,
+    pub unchanged: usize,
+    /// Entries that could not be read in any of the trees.
+    pub errors: usize,
+}
+
+impl MergeSummary {
+    fn count(&mut self, merge: &MergeNode) {
+        for child in &merge.children {
+            match child.change {
+                MergeChange::Unchanged => self.unchanged += 1,
+                MergeChange::ChangedInOurs
+                | MergeChange::AddedInOurs
+                | MergeChange::DeletedInOurs => self.changed_in_ours += 1,
+                MergeChange::ChangedInTheirs
+                | MergeChange::AddedInTheirs
+                | MergeChange::DeletedInTheirs => self.changed_in_theirs += 1,
+                MergeChange::ChangedInBoth => self.changed_in_both += 1,
+                MergeChange::Conflict => self.conflicts += 1,
+            }
+            if child.error.is_some() {
+                self.errors += 1;
+            }
+            self.count(child);
+        }
+    }
+}
+
+impl fmt::Display for MergeSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} changed in ours, {} changed in theirs, {} changed in both, {} conflicts, {} unchanged",
+            self.changed_in_ours,
+            self.changed_in_theirs,
+            self.changed_in_both,
+            self.conflicts,
+            self.unchanged
+        )?;
+        if self.errors > 0 {
+            write!(f, ", {} unreadable", self.errors)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compare our and their trees against the base tree they both come from. An entry is
+/// changed on a side when the two-way comparison with the base reports it, and the changes
+/// of both sides conflict unless the two-way comparison of our and their trees finds them
+/// identical. That comparison reads the contents of files of the same size, by hash unless
+/// they are compared byte by byte. Renames are not detected, entries are matched by path.
+pub fn compare_three_way(
+    base: &Node,
+    ours: &Node,
+    theirs: &Node,
+    options: &CompareOptions,
+) -> MergeNode {
+    let options = CompareOptions {
+        detect_renames: false,
+        ..options.clone()
+    };
+    let changed_paths = |left: &Node, right: &Node, options: &CompareOptions| {
+        let mut paths = HashSet::new();
+        collect_changed_paths(&compare_trees(left, right, options), &mut paths);
+        paths
+    };
+    // Edits of the same size on both sides must not pass for the same edit, so the two sides
+    // are compared by their contents even when the changes from the base are not
+    let between_options = CompareOptions {
+        compare_by: match options.compare_by {
+            CompareBy::Size | CompareBy::Mtime => CompareBy::Hash,
+            compare_by => compare_by,
+        },
+        ..options.clone()
+    };
+    let changes = ThreeWayChanges {
+        ours: changed_paths(base, ours, &options),
+        theirs: changed_paths(base, theirs, &options),
+        between: changed_paths(ours, theirs, &between_options),
+    };
+
+    let mut root = merge_node(
+        PathBuf::new(),
+        [Some(base), Some(ours), Some(theirs)],
+        &changes,
     );
-    compare_recursive(dir1, dir2, "");
+    root.name = base.name.clone();
+    root
+}
+
+/// Paths reported by the two-way comparisons of a three-way comparison.
+struct ThreeWayChanges {
+    ours: HashSet<PathBuf>,
+    theirs: HashSet<PathBuf>,
+    between: HashSet<PathBuf>,
+}
+
+fn collect_changed_paths(diff: &DiffNode, paths: &mut HashSet<PathBuf>) {
+    for child in &diff.children {
+        if child.change != Change::Unchanged {
+            paths.insert(child.path.clone());
+        }
+        collect_changed_paths(child, paths);
+    }
+}
+
+/// Build the entry at `path` from its entries in the base, our and their trees.
+fn merge_node(path: PathBuf, nodes: [Option<&Node>; 3], changes: &ThreeWayChanges) -> MergeNode {
+    let [base, ours, theirs] = nodes;
+    // Entries below one that changed type are only in some of the two-way comparisons,
+    // so being on one side only counts as a change too
+    let changed = |left: Option<&Node>, right: Option<&Node>, paths: &HashSet<PathBuf>| {
+        left.is_some() != right.is_some() || paths.contains(&path)
+    };
+    let ours_changed = changed(base, ours, &changes.ours);
+    let theirs_changed = changed(base, theirs, &changes.theirs);
+
+    let change = match (ours_changed, theirs_changed) {
+        (false, false) => MergeChange::Unchanged,
+        (true, false) if base.is_none() => MergeChange::AddedInOurs,
+        (true, false) if ours.is_none() => MergeChange::DeletedInOurs,
+        (true, false) => MergeChange::ChangedInOurs,
+        (false, true) if base.is_none() => MergeChange::AddedInTheirs,
+        (false, true) if theirs.is_none() => MergeChange::DeletedInTheirs,
+        (false, true) => MergeChange::ChangedInTheirs,
+        (true, true) if changed(ours, theirs, &changes.between) => MergeChange::Conflict,
+        (true, true) => MergeChange::ChangedInBoth,
+    };
+
+    let mut by_name: BTreeMap<&str, [Option<&Node>; 3]> = BTreeMap::new();
+    for (side, node) in nodes.iter().enumerate() {
+        for child in node.iter().flat_map(|node| &node.children) {
+            by_name.entry(&child.name).or_default()[side] = Some(child);
+        }
+    }
+    let children = by_name
+        .into_iter()
+        .map(|(name, nodes)| merge_node(path.join(name), nodes, changes))
+        .collect();
+
+    MergeNode {
+        name: base
+            .or(ours)
+            .or(theirs)
+            .map(|node| node.name.clone())
+            .unwrap_or_default(),
+        path,
+        change,
+        error: nodes.iter().flatten().find_map(|node| node.error.clone()),
+        base: base.map(entry_only),
+        ours: ours.map(entry_only),
+        theirs: theirs.map(entry_only),
+        children,
+    }
+}
+
+/// One side of a comparison.
+#[derive(Debug, Clone, Copy)]
+pub enum TreeSource<'a> {
+    /// A directory, scanned from disk.
+    Directory(&'a Path),
+    /// A snapshot written by the JSON output, with the path it was read from.
+    Snapshot(&'a TreeNode, &'a Path),
+}
+
+impl TreeSource<'_> {
+    /// Get the tree of this side, with the filters applied.
+    fn load(&self, options: &ScanOptions) -> Result<Node> {
+        match self {
+            TreeSource::Directory(dir) => scan(dir, options),
+            TreeSource::Snapshot(snapshot, path) => {
+                Ok(filter_tree(snapshot.to_tree(path), options))
+            }
+        }
+    }
+}
+
+/// Compare two directories or snapshots, with the same filters applied to both. A snapshot
+/// does not record the contents of the files, so they cannot be compared by hash or content
+/// nor checked for renames. Permissions are always compared against a snapshot, a changed
+/// mode is drift like any other change.
+pub fn compare_directories(
+    left: TreeSource,
+    right: TreeSource,
+    options: &ScanOptions,
+    compare_options: &CompareOptions,
+) -> Result<DiffNode> {
+    let reads_contents = matches!(
+        compare_options.compare_by,
+        CompareBy::Hash | CompareBy::Content
+    ) || compare_options.detect_renames;
+    for side in [left, right] {
+        match side {
+            // Both directories must be readable to compare them
+            TreeSource::Directory(dir) => {
+                fs::read_dir(dir).map_err(|source| TreeGenError::Path {
+                    path: dir.to_path_buf(),
+                    source,
+                })?;
+            }
+            TreeSource::Snapshot(_, path) if reads_contents => {
+                return Err(TreeGenError::Compare(format!(
+                    "'{}' is a snapshot without file contents, compare it by size or mtime",
+                    path.display()
+                )));
+            }
+            TreeSource::Snapshot(..) => {}
+        }
+    }
+
+    let mut compare_options = compare_options.clone();
+    let has_snapshot = [left, right]
+        .iter()
+        .any(|side| matches!(side, TreeSource::Snapshot(..)));
+    if has_snapshot && !compare_options.attributes.contains(&Attribute::Mode) {
+        compare_options.attributes.push(Attribute::Mode);
+    }
+
+    let left = left.load(options)?;
+    let right = right.load(options)?;
+    Ok(compare_trees(&left, &right, &compare_options))
+}
+
+/// Compare a directory against a text tree saved from the ascii output, with the same filters
+/// applied to both. A text tree does not record exact sizes, so only names and kinds are compared,
+/// and any other way to compare given in `compare_options` is an error.
+pub fn compare_with_text_tree(
+    dir: &Path,
+    tree_file: &Path,
+    options: &ScanOptions,
+    compare_options: &CompareOptions,
+) -> Result<DiffNode> {
+    let unsupported = if compare_options.compare_by != CompareBy::default() {
+        Some(format!(
+            "--compare-by {}",
+            compare_options.compare_by.name()
+        ))
+    } else if compare_options.detect_renames {
+        Some("--detect-renames".to_string())
+    } else if !compare_options.attributes.is_empty() {
+        Some("--compare-attributes".to_string())
+    } else {
+        None
+    };
+    if let Some(option) = unsupported {
+        return Err(TreeGenError::Compare(format!(
+            "'{}' is a text tree that only records names and kinds, it cannot be compared with {}",
+            tree_file.display(),
+            option
+        )));
+    }
+
+    let text = fs::read_to_string(tree_file).map_err(|source| TreeGenError::Path {
+        path: tree_file.to_path_buf(),
+        source,
+    })?;
+    let mut right = parse_tree(&text, tree_file)?;
+    right.path = tree_file.to_path_buf();
+    let right = filter_tree(right, options);
+
+    let left = scan(dir, options)?;
+    let compare_options = CompareOptions {
+        compare_by: CompareBy::Structure,
+        ..CompareOptions::default()
+    };
+    Ok(compare_trees(&left, &right, &compare_options))
+}
+
+/// Scan a base directory and our and their directories with the same filters, and compare
+/// them three ways.
+pub fn compare_directories_three_way(
+    base: &Path,
+    ours: &Path,
+    theirs: &Path,
+    options: &ScanOptions,
+    compare_options: &CompareOptions,
+) -> Result<MergeNode> {
+    // Every side must be a readable directory to compare them
+    for dir in [base, ours, theirs] {
+        fs::read_dir(dir).map_err(|source| TreeGenError::Path {
+            path: dir.to_path_buf(),
+            source,
+        })?;
+    }
+
+    let base = scan(base, options)?;
+    let ours = scan(ours, options)?;
+    let theirs = scan(theirs, options)?;
+    Ok(compare_three_way(&base, &ours, &theirs, compare_options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::restore::tests::Scratch;
+
+    /// Write files with their contents below `root`, creating their directories.
+    fn write_files(root: &Path, files: &[(&str, &str)]) {
+        for (path, contents) in files {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+    }
+
+    fn compare_files(name: &str, left: &[(&str, &str)], right: &[(&str, &str)]) -> DiffNode {
+        let scratch = Scratch::new(name);
+        let (left_dir, right_dir) = (scratch.dest.join("left"), scratch.dest.join("right"));
+        write_files(&left_dir, left);
+        write_files(&right_dir, right);
+        let options = ScanOptions::default();
+        let compare_options = CompareOptions {
+            detect_renames: true,
+            ..CompareOptions::default()
+        };
+        compare_trees(
+            &scan(&left_dir, &options).unwrap(),
+            &scan(&right_dir, &options).unwrap(),
+            &compare_options,
+        )
+    }
+
+    /// Every entry below the root as its path, change and where it was moved from.
+    fn changes(diff: &DiffNode) -> Vec<(String, Change, Option<String>)> {
+        diff.children
+            .iter()
+            .flat_map(|child| {
+                let from = child
+                    .moved_from
+                    .as_ref()
+                    .map(|from| from.display().to_string());
+                let change = (child.path.display().to_string(), child.change, from);
+                std::iter::once(change).chain(changes(child))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn detects_renames_and_moves() {
+        let diff = compare_files(
+            "compare_renames",
+            &[
+                ("a/one.txt", "hello"),
+                ("b/two.txt", "world!"),
+                ("b/keep", ""),
+            ],
+            &[
+                ("a/uno.txt", "hello"),
+                ("b/keep", ""),
+                ("c/two.txt", "world!"),
+            ],
+        );
+        assert_eq!(
+            changes(&diff),
+            [
+                ("a".to_string(), Change::Unchanged, None),
+                (
+                    "a/uno.txt".to_string(),
+                    Change::Moved,
+                    Some("a/one.txt".to_string())
+                ),
+                ("b".to_string(), Change::Unchanged, None),
+                ("b/keep".to_string(), Change::Unchanged, None),
+                ("c".to_string(), Change::Added, None),
+                (
+                    "c/two.txt".to_string(),
+                    Change::Moved,
+                    Some("b/two.txt".to_string())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn moves_to_the_most_similar_name() {
+        let diff = compare_files(
+            "compare_rename_similarity",
+            &[("x/report.csv", "data")],
+            &[
+                ("y/notes.txt", "data"),
+                ("y/report.csv", "data"),
+                ("y/report.txt", "data"),
+                ("z/report.csv", "data"),
+            ],
+        );
+        let moved: Vec<_> = changes(&diff)
+            .into_iter()
+            .filter(|(_, change, _)| *change == Change::Moved)
+            .collect();
+        // The same file name in both directories ties, the first path wins
+        assert_eq!(
+            moved,
+            [(
+                "y/report.csv".to_string(),
+                Change::Moved,
+                Some("x/report.csv".to_string())
+            )]
+        );
+    }
+
+    #[test]
+    fn moves_each_file_once() {
+        let diff = compare_files(
+            "compare_rename_duplicates",
+            &[
+                ("old1.txt", "same"),
+                ("old2.txt", "same"),
+                ("other.txt", "size"),
+            ],
+            &[
+                ("new1.txt", "same"),
+                ("new2.txt", "same"),
+                ("new3.txt", "same"),
+            ],
+        );
+        assert_eq!(
+            changes(&diff),
+            [
+                (
+                    "new1.txt".to_string(),
+                    Change::Moved,
+                    Some("old1.txt".to_string())
+                ),
+                (
+                    "new2.txt".to_string(),
+                    Change::Moved,
+                    Some("old2.txt".to_string())
+                ),
+                ("new3.txt".to_string(), Change::Added, None),
+                ("other.txt".to_string(), Change::Removed, None),
+            ]
+        );
+    }
+
+    /// Every entry below the root as its path and three-way change.
+    fn merge_changes(merge: &MergeNode) -> Vec<(String, MergeChange)> {
+        merge
+            .children
+            .iter()
+            .flat_map(|child| {
+                let change = (child.path.display().to_string(), child.change);
+                std::iter::once(change).chain(merge_changes(child))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn classifies_three_way_changes() {
+        // Edits change the sizes, so no file has to be read
+        let base = "base\n├── both.txt (1.00 KB)\n├── conflict.txt (1.00 KB)\n├── dir\n│   └── nested.txt (1.00 KB)\n├── ours-deleted.txt (1.00 KB)\n├── ours.txt (1.00 KB)\n├── retyped (1.00 KB)\n├── same.txt (1.00 KB)\n├── theirs-deleted.txt (1.00 KB)\n└── theirs.txt (1.00 KB)\n";
+        let ours = "ours\n├── conflict.txt (2.00 KB)\n├── dir (1.00 KB)\n├── ours-added.txt (1.00 KB)\n├── ours.txt (2.00 KB)\n├── retyped/\n├── same.txt (1.00 KB)\n├── theirs-deleted.txt (1.00 KB)\n└── theirs.txt (1.00 KB)\n";
+        let theirs = "theirs\n├── conflict.txt (3.00 KB)\n├── dir\n│   └── nested.txt (2.00 KB)\n├── ours-deleted.txt (1.00 KB)\n├── ours.txt (1.00 KB)\n├── retyped/\n├── same.txt (1.00 KB)\n├── theirs-added.txt (1.00 KB)\n└── theirs.txt (2.00 KB)\n";
+        let parse = |text| parse_tree(text, Path::new("tree.txt")).unwrap();
+
+        let merge = compare_three_way(
+            &parse(base),
+            &parse(ours),
+            &parse(theirs),
+            &CompareOptions::default(),
+        );
+        assert_eq!(
+            merge_changes(&merge),
+            [
+                ("both.txt".to_string(), MergeChange::ChangedInBoth),
+                ("conflict.txt".to_string(), MergeChange::Conflict),
+                ("dir".to_string(), MergeChange::ChangedInOurs),
+                // Gone from ours with the directory that became a file, changed in theirs
+                ("dir/nested.txt".to_string(), MergeChange::Conflict),
+                ("ours-added.txt".to_string(), MergeChange::AddedInOurs),
+                ("ours-deleted.txt".to_string(), MergeChange::DeletedInOurs),
+                ("ours.txt".to_string(), MergeChange::ChangedInOurs),
+                ("retyped".to_string(), MergeChange::ChangedInBoth),
+                ("same.txt".to_string(), MergeChange::Unchanged),
+                ("theirs-added.txt".to_string(), MergeChange::AddedInTheirs),
+                (
+                    "theirs-deleted.txt".to_string(),
+                    MergeChange::DeletedInTheirs
+                ),
+                ("theirs.txt".to_string(), MergeChange::ChangedInTheirs),
+            ]
+        );
+    }
+
+    #[test]
+    fn compares_snapshots_with_extreme_times() {
+        let scratch = Scratch::new("compare_extreme_times");
+        let dir = scratch.dest.join("dir");
+        write_files(&dir, &[("file", "contents")]);
+        let snapshot_path = scratch.dest.join("snapshot.json");
+        let options = ScanOptions::default();
+        let compare_options = CompareOptions {
+            compare_by: CompareBy::Mtime,
+            attributes: vec![Attribute::Mtime],
+            ..CompareOptions::default()
+        };
+
+        // Write a snapshot of the directory with the time of its file replaced
+        let write_snapshot = |epoch| {
+            let mut snapshot = TreeNode::from_tree(&scan(&dir, &options).unwrap(), None);
+            snapshot.children[0].modified_epoch = Some(epoch);
+            fs::write(&snapshot_path, serde_json::to_string(&snapshot).unwrap()).unwrap();
+            snapshot
+        };
+
+        for epoch in [-62167219200, 253402300799] {
+            write_snapshot(epoch);
+            assert!(TreeNode::read_snapshot(&snapshot_path).is_ok());
+        }
+        for epoch in [i64::MIN, -62167219201, 253402300800, i64::MAX] {
+            let snapshot = write_snapshot(epoch);
+
+            // Loading the snapshot refuses times the JSON output never writes
+            let result = TreeNode::read_snapshot(&snapshot_path);
+            assert!(
+                matches!(result, Err(TreeGenError::Parse { .. })),
+                "{} was loaded",
+                epoch
+            );
+
+            // A snapshot built another way still compares without overflowing
+            let diff = compare_directories(
+                TreeSource::Snapshot(&snapshot, &snapshot_path),
+                TreeSource::Directory(&dir),
+                &options,
+                &compare_options,
+            )
+            .unwrap();
+            assert_eq!(diff.children[0].change, Change::Modified);
+            assert_eq!(diff.children[0].changed_attributes, [Attribute::Mtime]);
+        }
+    }
 }
@@ -1,105 +1,440 @@
 use std::{
-    fs::metadata,
-    path::Path,
-    time::{SystemTime, UNIX_EPOCH},
+    fmt::Write,
+    ops::RangeInclusive,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
-pub fn get_human_readable_date(path: &Path) -> String {
-    match metadata(path) {
-        Ok(metadata) => match metadata.modified() {
-            Ok(time) => {
-                // Calculate the duration since UNIX_EPOCH
-                let duration = time
-                    .duration_since(UNIX_EPOCH)
-                    .expect("Time went backwards");
+use super::{
+    error::{Result, TreeGenError},
+    scan::{Node, TimeField},
+    timezone::TimeZone,
+};
+
+/// Pattern of the human readable dates, unless `--time-format` says otherwise.
+pub const DEFAULT_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Timestamps of the first and last second of the years 0000 to 9999, the dates that are
+/// parsed and written with four digit years.
+pub const TIMESTAMP_RANGE: RangeInclusive<i64> = -62167219200..=253402300799;
+
+const WEEKDAYS: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// How the human readable outputs show times: a strftime-style pattern in a time zone.
+#[derive(Debug, Clone)]
+pub struct TimeFormat {
+    pattern: String,
+    zone: TimeZone,
+}
+
+impl Default for TimeFormat {
+    /// The default pattern in UTC.
+    fn default() -> Self {
+        TimeFormat::new(DEFAULT_TIME_FORMAT, TimeZone::utc())
+    }
+}
 
-                // Convert the duration to seconds and nanoseconds
-                let secs = duration.as_secs();
-                let nanos = duration.subsec_nanos();
+impl TimeFormat {
+    pub fn new(pattern: &str, zone: TimeZone) -> Self {
+        TimeFormat {
+            pattern: pattern.to_string(),
+            zone,
+        }
+    }
 
-                // Convert seconds to a standard date-time
-                let datetime = UNIX_EPOCH + std::time::Duration::new(secs, nanos);
-                let datetime = datetime_to_readable(datetime);
+    /// Format a time with the pattern, like `strftime` does. Supported are `%Y %C %y %m %B
+    /// %b %h %d %e %j %H %I %M %S %p %a %A %u %w %Z %z %s %F %T %R %D %c %n %t %%`, other
+    /// sequences are written as they are.
+    pub fn format(&self, system_time: SystemTime) -> String {
+        let secs = unix_timestamp(system_time);
+        let local = self.zone.local_time_type(secs);
+        let local_secs = secs + local.offset as i64;
+        let (year, month, day, hour, minute, second) = civil_from_timestamp(local_secs);
+        let weekday = day_of_week(local_secs.div_euclid(86_400)) as usize;
+        let month_name = MONTHS[month as usize - 1];
 
-                datetime
+        let mut formatted = String::new();
+        let mut chars = self.pattern.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                formatted.push(c);
+                continue;
             }
-            Err(_) => "modified date unknown".to_string(),
-        },
-        Err(_) => "modified date unknown".to_string(),
+            // Writing to a string cannot fail
+            let _ = match chars.next() {
+                Some('Y') => write!(formatted, "{}", year),
+                Some('C') => write!(formatted, "{:02}", year.div_euclid(100)),
+                Some('y') => write!(formatted, "{:02}", year.rem_euclid(100)),
+                Some('m') => write!(formatted, "{:02}", month),
+                Some('B') => write!(formatted, "{}", month_name),
+                Some('b' | 'h') => write!(formatted, "{}", &month_name[..3]),
+                Some('d') => write!(formatted, "{:02}", day),
+                Some('e') => write!(formatted, "{:2}", day),
+                Some('j') => write!(formatted, "{:03}", days_in_months(year, month - 1) + day),
+                Some('H') => write!(formatted, "{:02}", hour),
+                Some('I') => write!(formatted, "{:02}", (hour + 11) % 12 + 1),
+                Some('M') => write!(formatted, "{:02}", minute),
+                Some('S') => write!(formatted, "{:02}", second),
+                Some('p') => write!(formatted, "{}", if hour < 12 { "AM" } else { "PM" }),
+                Some('a') => write!(formatted, "{}", &WEEKDAYS[weekday][..3]),
+                Some('A') => write!(formatted, "{}", WEEKDAYS[weekday]),
+                Some('u') => write!(formatted, "{}", (weekday + 6) % 7 + 1),
+                Some('w') => write!(formatted, "{}", weekday),
+                Some('Z') => write!(formatted, "{}", local.abbreviation),
+                Some('z') => {
+                    let offset = local.offset.unsigned_abs() / 60;
+                    let sign = if local.offset < 0 { '-' } else { '+' };
+                    write!(formatted, "{}{:02}{:02}", sign, offset / 60, offset % 60)
+                }
+                Some('s') => write!(formatted, "{}", secs),
+                Some('F') => write!(formatted, "{}-{:02}-{:02}", year, month, day),
+                Some('T') => write!(formatted, "{:02}:{:02}:{:02}", hour, minute, second),
+                Some('R') => write!(formatted, "{:02}:{:02}", hour, minute),
+                Some('D') => write!(
+                    formatted,
+                    "{:02}/{:02}/{:02}",
+                    month,
+                    day,
+                    year.rem_euclid(100)
+                ),
+                Some('c') => write!(
+                    formatted,
+                    "{} {} {:2} {:02}:{:02}:{:02} {}",
+                    &WEEKDAYS[weekday][..3],
+                    &month_name[..3],
+                    day,
+                    hour,
+                    minute,
+                    second,
+                    year
+                ),
+                Some('n') => writeln!(formatted),
+                Some('t') => write!(formatted, "\t"),
+                Some('%') => write!(formatted, "%"),
+                Some(other) => write!(formatted, "%{}", other),
+                None => write!(formatted, "%"),
+            };
+        }
+        formatted
+    }
+}
+
+/// Format a timestamp of an entry, or tell which one is unknown.
+pub fn get_human_readable_date(node: &Node, field: TimeField, format: &TimeFormat) -> String {
+    match field.of(node) {
+        Some(time) => format.format(time),
+        None => {
+            let date = match field {
+                TimeField::Modified => "modified",
+                TimeField::Accessed => "access",
+                TimeField::Changed => "change",
+                TimeField::Created => "creation",
+            };
+            format!("{} date unknown", date)
+        }
     }
 }
 
+/// Format a time as `YYYY-MM-DD HH:MM:SS` in UTC.
 pub fn datetime_to_readable(system_time: SystemTime) -> String {
-    // Convert `SystemTime` to a UNIX timestamp (seconds and nanoseconds)
-    let duration = system_time
-        .duration_since(UNIX_EPOCH)
-        .expect("Time went backwards");
-    let secs = duration.as_secs() as i64;
-
-    // Extract individual date and time components
-    let year = 1970 + secs / 31_536_000; // rough approximation of years from UNIX epoch
-    let month = ((secs % 31_536_000) / 2_592_000) + 1; // Months from seconds
-    let day = ((secs % 2_592_000) / 86_400) + 1; // Days from seconds
-    let hour = (secs % 86_400) / 3_600; // Hours from seconds
-    let minute = (secs % 3_600) / 60;
-    let second = secs % 60;
-
-    // Format as a readable string
+    TimeFormat::default().format(system_time)
+}
+
+/// Get the number of whole seconds since the UNIX epoch, negative for times before it.
+/// Times between two seconds round down, and times out of range saturate.
+pub fn unix_timestamp(system_time: SystemTime) -> i64 {
+    match system_time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => i64::try_from(duration.as_secs()).unwrap_or(i64::MAX),
+        Err(err) => {
+            let before = err.duration();
+            let secs = before.as_secs() + u64::from(before.subsec_nanos() > 0);
+            0i64.checked_sub_unsigned(secs).unwrap_or(i64::MIN)
+        }
+    }
+}
+
+/// Get the time a number of seconds since the UNIX epoch, negative for times before it.
+pub fn from_unix_timestamp(secs: i64) -> SystemTime {
+    let offset = Duration::from_secs(secs.unsigned_abs());
+    match secs >= 0 {
+        true => UNIX_EPOCH + offset,
+        false => UNIX_EPOCH - offset,
+    }
+}
+
+/// Format a time as an ISO-8601 UTC timestamp, e.g. `2024-02-29T13:45:00Z`.
+pub fn datetime_to_iso8601(system_time: SystemTime) -> String {
+    let (year, month, day, hour, minute, second) =
+        civil_from_timestamp(unix_timestamp(system_time));
     format!(
-        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
         year, month, day, hour, minute, second
     )
 }
 
-// TODO! Need to add  more filter and restrict for date format filter value
-/// Parses a date in "YYYY-MM-DD" format to a timestamp.
-pub fn parse_date(date: &str) -> u64 {
-    // Split date components from "YYYY-MM-DD" format
-    let parts: Vec<&str> = date.split('-').collect();
-    if parts.len() != 3 {
-        panic!("Invalid date format, expected YYYY-MM-DD");
-    }
+/// Converts seconds since the UNIX epoch to the UTC (year, month, day, hour, minute, second).
+fn civil_from_timestamp(secs: i64) -> (i32, u32, i64, i64, i64, i64) {
+    let secs_of_day = secs.rem_euclid(86_400);
+
+    // Count in eras of 400 years from March 1 of year 0, every era has the same days
+    // (Howard Hinnant's civil_from_days)
+    let days = secs.div_euclid(86_400) + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = match month_from_march < 10 {
+        true => month_from_march + 3,
+        false => month_from_march - 9,
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+
+    (
+        // Saturate the years of times hundreds of millions of years away
+        year.clamp(i32::MIN.into(), i32::MAX.into()) as i32,
+        month as u32,
+        day,
+        secs_of_day / 3_600,
+        (secs_of_day % 3_600) / 60,
+        secs_of_day % 60,
+    )
+}
 
-    // Parse year, month, and day as integers
-    let year: i32 = parts[0].parse().expect("Invalid year");
-    let month: u32 = parts[1].parse().expect("Invalid month");
-    let day: u32 = parts[2].parse().expect("Invalid day");
+/// Get the UTC year of a number of seconds since the UNIX epoch.
+pub(crate) fn year_of_timestamp(secs: i64) -> i32 {
+    civil_from_timestamp(secs).0
+}
 
-    // Validate and calculate the number of days since the UNIX epoch
-    let days_since_epoch =
-        days_since_unix_epoch(year, month, day).expect("Invalid date or calculation error");
+/// Get the number of days from 1970-01-01 to a date, negative for dates before it.
+pub(crate) fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+    // Years start on March 1 so the leap day is the last one (Howard Hinnant's days_from_civil)
+    let year = year as i64 - (month <= 2) as i64;
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_from_march = (month as i64 + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
 
-    // Convert days to seconds and return as timestamp
-    (days_since_epoch * 86400) as u64
+/// Get the day of the week of a number of days since the UNIX epoch, 0 for Sunday.
+pub(crate) fn day_of_week(days: i64) -> i64 {
+    // 1970-01-01 was a Thursday
+    (days + 4).rem_euclid(7)
 }
 
-/// Calculates the number of days since the UNIX epoch (1970-01-01) for a given date.
-fn days_since_unix_epoch(year: i32, month: u32, day: u32) -> Option<i64> {
-    // Define constants
-    const UNIX_EPOCH_YEAR: i32 = 1970;
-    const DAYS_IN_COMMON_YEAR: i64 = 365;
-    const DAYS_IN_LEAP_YEAR: i64 = 366;
+/// Get the number of days in a month, from 1 for January to 12 for December.
+pub(crate) fn days_in_month(year: i32, month: u32) -> u32 {
+    (days_in_months(year, month) - days_in_months(year, month - 1)) as u32
+}
 
-    // Check for valid date ranges
-    if year < UNIX_EPOCH_YEAR || month == 0 || month > 12 || day == 0 || day > 31 {
-        return None;
+/// A span of time given by the user, from `start` up to but excluding `end`, in seconds
+/// since the UNIX epoch. A date spans its whole day and a time the minute or second it is
+/// given to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeSpan {
+    pub start: i64,
+    pub end: i64,
+}
+
+/// Parse a date like `2024-03-01`, optionally followed by `T` or a space and a time like
+/// `14:30` or `14:30:15`, and by a time zone `Z`, `UTC` or an offset like `+02:00`. Dates
+/// and times without a zone are in `zone`. `@SECONDS` is a UNIX timestamp.
+pub fn parse_time(value: &str, zone: &TimeZone) -> Result<TimeSpan> {
+    let invalid = |reason: String| TreeGenError::InvalidDate {
+        value: value.trim().to_string(),
+        reason,
+    };
+    let value = value.trim();
+
+    if let Some(secs) = value.strip_prefix('@') {
+        let secs: i64 = secs
+            .parse()
+            .map_err(|_| invalid("expected a number of seconds after '@'".to_string()))?;
+        let end = secs
+            .checked_add(1)
+            .ok_or_else(|| invalid("timestamp is too large".to_string()))?;
+        return Ok(TimeSpan { start: secs, end });
     }
 
-    // Determine the total number of days from 1970-01-01 to the given date
-    let mut days = 0;
+    let (date, rest) = match value.find(['T', ' ']) {
+        Some(i) => (&value[..i], value[i + 1..].trim_start()),
+        None => (value, ""),
+    };
+    let parts: Vec<&str> = date.split('-').collect();
+    let [year, month, day] = parts.as_slice() else {
+        return Err(invalid("expected a date like 2024-03-01".to_string()));
+    };
+    let year: i32 = year
+        .parse()
+        .ok()
+        .filter(|year| (0..=9999).contains(year))
+        .ok_or_else(|| invalid(format!("invalid year '{}', expected 0000 to 9999", year)))?;
+    let month: u32 = month
+        .parse()
+        .ok()
+        .filter(|month| (1..=12).contains(month))
+        .ok_or_else(|| invalid(format!("invalid month '{}', expected 01 to 12", month)))?;
+    let days = days_in_month(year, month);
+    let day: u32 = day
+        .parse()
+        .ok()
+        .filter(|day| (1..=days).contains(day))
+        .ok_or_else(|| {
+            invalid(format!(
+                "invalid day '{}', {} {} has {} days",
+                day,
+                MONTHS[month as usize - 1],
+                year,
+                days
+            ))
+        })?;
 
-    for y in UNIX_EPOCH_YEAR..year {
-        days += if is_leap_year(y) {
-            DAYS_IN_LEAP_YEAR
-        } else {
-            DAYS_IN_COMMON_YEAR
-        };
+    // The time runs up to the zone, which may follow it without a space
+    let time_end = rest
+        .find(|c: char| !c.is_ascii_digit() && c != ':')
+        .unwrap_or(rest.len());
+    let (time, zone_name) = (&rest[..time_end], rest[time_end..].trim());
+
+    let mut local = days_from_civil(year, month, day) * 86_400;
+    let length = match time.is_empty() {
+        true => 86_400,
+        false => {
+            let fields: Vec<&str> = time.split(':').collect();
+            let limits = [23, 59, 59];
+            if !(2..=3).contains(&fields.len()) {
+                return Err(invalid(format!(
+                    "invalid time '{}', expected HH:MM or HH:MM:SS",
+                    time
+                )));
+            }
+            for ((field, limit), unit) in fields.iter().zip(limits).zip([3_600, 60, 1]) {
+                let number: i64 = field
+                    .parse()
+                    .ok()
+                    .filter(|number| (0..=limit).contains(number))
+                    .ok_or_else(|| {
+                        invalid(format!(
+                            "invalid time '{}', expected HH:MM or HH:MM:SS",
+                            time
+                        ))
+                    })?;
+                local += number * unit;
+            }
+            [60, 1][fields.len() - 2]
+        }
+    };
+
+    let offset = match zone_name {
+        "" => None,
+        "Z" | "UTC" | "GMT" => Some(0),
+        offset => Some(parse_offset(offset).ok_or_else(|| {
+            invalid(format!(
+                "invalid time zone '{}', expected Z, UTC or an offset like +02:00",
+                offset
+            ))
+        })?),
+    };
+    let to_utc = |local: i64| match offset {
+        Some(offset) => local - offset,
+        None => zone.utc_from_local(local),
+    };
+    Ok(TimeSpan {
+        start: to_utc(local),
+        end: to_utc(local + length),
+    })
+}
+
+/// Parse an offset from UTC like `+02:00`, `+0200` or `-05` into seconds.
+fn parse_offset(offset: &str) -> Option<i64> {
+    let sign = match offset.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let digits = offset[1..].replace(':', "");
+    if !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
     }
+    let (hours, minutes) = match digits.len() {
+        2 => (digits.parse::<i64>().ok()?, 0),
+        4 => (digits[..2].parse().ok()?, digits[2..].parse().ok()?),
+        _ => return None,
+    };
+    (hours <= 23 && minutes <= 59).then_some(sign * (hours * 3_600 + minutes * 60))
+}
 
-    // Add the days for the given month and day in the target year
-    days += days_in_months(year, month - 1) + day as i64 - 1;
+/// Parse a duration like `7d`, `2w` or `1h30m` into seconds. The units are `s`, `m`, `h`,
+/// `d` and `w`, or their names like `days`.
+pub fn parse_duration(value: &str) -> Result<i64> {
+    let invalid = |reason: String| TreeGenError::InvalidDate {
+        value: value.trim().to_string(),
+        reason,
+    };
+    let compact: String = value.chars().filter(|c| !c.is_whitespace()).collect();
+    if compact.is_empty() {
+        return Err(invalid("expected a duration like 7d or 2w".to_string()));
+    }
 
-    Some(days)
+    let mut seconds: i64 = 0;
+    let mut rest = compact.as_str();
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let unit_end = rest[digits..]
+            .find(|c: char| c.is_ascii_digit())
+            .map_or(rest.len(), |i| digits + i);
+        let (number, unit) = (&rest[..digits], &rest[digits..unit_end]);
+        let number: i64 = number
+            .parse()
+            .map_err(|_| invalid("expected a number before each unit".to_string()))?;
+        let unit_seconds = match unit {
+            "s" | "sec" | "secs" | "second" | "seconds" => 1,
+            "m" | "min" | "mins" | "minute" | "minutes" => 60,
+            "h" | "hour" | "hours" => 3_600,
+            "d" | "day" | "days" => 86_400,
+            "w" | "week" | "weeks" => 604_800,
+            "" => return Err(invalid(format!("missing a unit after {}", number))),
+            unit => {
+                return Err(invalid(format!(
+                    "unknown unit '{}', expected s, m, h, d or w",
+                    unit
+                )))
+            }
+        };
+        seconds = number
+            .checked_mul(unit_seconds)
+            .and_then(|part| seconds.checked_add(part))
+            .ok_or_else(|| invalid("duration is too long".to_string()))?;
+        rest = &rest[unit_end..];
+    }
+    Ok(seconds)
 }
 
 /// Checks if a year is a leap year.
@@ -112,8 +447,8 @@ fn days_in_months(year: i32, month: u32) -> i64 {
     const DAYS_IN_MONTH: [i64; 12] = [31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
 
     let mut days = 0;
-    for i in 0..month as usize {
-        days += DAYS_IN_MONTH[i];
+    for (i, days_in_month) in DAYS_IN_MONTH.iter().enumerate().take(month as usize) {
+        days += days_in_month;
         // Add a day for February if it's a leap year
         if i == 1 && is_leap_year(year) {
             days += 1;
@@ -121,3 +456,259 @@ fn days_in_months(year: i32, month: u32) -> i64 {
     }
     days
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BERLIN: &str = "CET-1CEST,M3.5.0,M10.5.0/3";
+
+    fn berlin() -> TimeZone {
+        TimeZone::from_tz(BERLIN).unwrap()
+    }
+
+    #[test]
+    fn converts_timestamps_to_utc_dates() {
+        let cases = [
+            (0, "1970-01-01T00:00:00Z"),
+            (-1, "1969-12-31T23:59:59Z"),
+            (-2203891200, "1900-03-01T00:00:00Z"),
+            (-11670955200, "1600-02-29T12:00:00Z"),
+            (-12187756800, "1583-10-15T00:00:00Z"),
+            (-62135596800, "0001-01-01T00:00:00Z"),
+            (951868799, "2000-02-29T23:59:59Z"),
+            (1735689599, "2024-12-31T23:59:59Z"),
+            (253402300799, "9999-12-31T23:59:59Z"),
+        ];
+        for (secs, iso) in cases {
+            assert_eq!(datetime_to_iso8601(from_unix_timestamp(secs)), iso);
+            assert_eq!(unix_timestamp(from_unix_timestamp(secs)), secs);
+        }
+        assert_eq!(
+            datetime_to_readable(from_unix_timestamp(-1)),
+            "1969-12-31 23:59:59"
+        );
+    }
+
+    #[test]
+    fn names_the_unknown_date() {
+        // Text trees record no times
+        let tree = crate::parse::parse_tree("root\n", std::path::Path::new("tree.txt")).unwrap();
+        let cases = [
+            (TimeField::Modified, "modified date unknown"),
+            (TimeField::Accessed, "access date unknown"),
+            (TimeField::Changed, "change date unknown"),
+            (TimeField::Created, "creation date unknown"),
+        ];
+        for (field, expected) in cases {
+            assert_eq!(
+                get_human_readable_date(&tree, field, &TimeFormat::default()),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn converts_extreme_times_to_timestamps() {
+        for secs in [i64::MIN, i64::MIN + 1, i64::MAX] {
+            assert_eq!(unix_timestamp(from_unix_timestamp(secs)), secs);
+        }
+        let half = Duration::from_millis(500);
+        assert_eq!(unix_timestamp(UNIX_EPOCH - half), -1);
+        assert_eq!(unix_timestamp(UNIX_EPOCH + half), 0);
+        assert_eq!(
+            unix_timestamp(from_unix_timestamp(-1) - half),
+            -2,
+            "pre-epoch times round down"
+        );
+    }
+
+    #[test]
+    fn formats_like_strftime() {
+        let winter = from_unix_timestamp(1709216100);
+        let summer = from_unix_timestamp(1720000000);
+        let cases = [
+            (winter, "%F %T", "2024-02-29 15:15:00"),
+            (winter, "%Y %C %y %m %d %e %j", "2024 20 24 02 29 29 060"),
+            (
+                winter,
+                "%B %b %h %a %A %u %w",
+                "February Feb Feb Thu Thursday 4 4",
+            ),
+            (winter, "%H %I %M %S %p %R", "15 03 15 00 PM 15:15"),
+            (winter, "%Z %z %s", "CET +0100 1709216100"),
+            (winter, "%D|%c", "02/29/24|Thu Feb 29 15:15:00 2024"),
+            (winter, "%%|%q|%n|%t|%", "%|%q|\n|\t|%"),
+            (summer, "%e %j %I %p %Z %z", " 3 185 11 AM CEST +0200"),
+            (summer, "%c", "Wed Jul  3 11:46:40 2024"),
+        ];
+        for (time, pattern, expected) in cases {
+            assert_eq!(TimeFormat::new(pattern, berlin()).format(time), expected);
+        }
+
+        let new_york = TimeZone::from_tz("EST5EDT,M3.2.0,M11.1.0").unwrap();
+        assert_eq!(
+            TimeFormat::new("%F %T %Z %z %a %j", new_york).format(from_unix_timestamp(-1)),
+            "1969-12-31 18:59:59 EST -0500 Wed 365"
+        );
+    }
+
+    #[test]
+    fn converts_dates_to_days_and_back() {
+        let cases = [
+            ((1970, 1, 1), 0),
+            ((1969, 12, 31), -1),
+            ((2000, 2, 29), 11_016),
+            ((2000, 3, 1), 11_017),
+            ((1900, 3, 1), -25_508),
+            ((0, 1, 1), -719_528),
+            ((0, 2, 29), -719_469),
+            ((9999, 12, 31), 2_932_896),
+        ];
+        for ((year, month, day), days) in cases {
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+
+        // Every day of every year, in steps that reach all months and days of the week
+        for days in (-719_528..=2_932_896).step_by(97) {
+            let (year, month, day, ..) = civil_from_timestamp(days * 86_400);
+            assert_eq!(days_from_civil(year, month, day as u32), days);
+            assert!((1..=days_in_month(year, month) as i64).contains(&day));
+        }
+        // Far out of the range of dates, without looping over every year
+        for year in [-1_000_000, 1_000_000, i32::MIN, i32::MAX] {
+            assert_eq!(
+                year_of_timestamp(days_from_civil(year, 6, 15) * 86_400),
+                year
+            );
+        }
+        assert_eq!(year_of_timestamp(i64::MAX), i32::MAX);
+        assert_eq!(year_of_timestamp(i64::MIN), i32::MIN);
+    }
+
+    #[test]
+    fn parses_times() {
+        let utc = TimeZone::utc();
+        let cases = [
+            ("2024-02-29", 1709164800, 86_400),
+            ("  2024-03-01  ", 1709251200, 86_400),
+            ("1969-12-31", -86_400, 86_400),
+            ("0000-01-01", -62167219200, 86_400),
+            ("1900-02-28T23:59", -2203891260, 60),
+            ("2024-03-01 14:30", 1709303400, 60),
+            ("2024-03-01T14:30:15Z", 1709303415, 1),
+            ("2024-03-01T14:30UTC", 1709303400, 60),
+            ("2024-03-01 14:30 GMT", 1709303400, 60),
+            ("2024-03-01T14:30+02:00", 1709303400 - 7_200, 60),
+            ("2024-03-01T14:30 -0530", 1709303400 + 19_800, 60),
+            ("2024-03-01 +05", 1709251200 - 18_000, 86_400),
+            ("9999-12-31T23:59:59Z", 253402300799, 1),
+            ("@0", 0, 1),
+            ("@-1", -1, 1),
+        ];
+        for (value, start, length) in cases {
+            assert_eq!(
+                parse_time(value, &utc).unwrap(),
+                TimeSpan {
+                    start,
+                    end: start + length
+                },
+                "{}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn parses_times_in_the_local_zone() {
+        let berlin = berlin();
+        let cases = [
+            ("2024-03-01 14:30", 1709303400 - 3_600, 1709303400 - 3_540),
+            // Days when the clocks change last 23 and 25 hours
+            ("2024-03-31", 1711839600, 1711922400),
+            ("2024-10-27", 1729980000, 1730070000),
+            ("2024-03-01T14:30Z", 1709303400, 1709303460),
+        ];
+        for (value, start, end) in cases {
+            assert_eq!(
+                parse_time(value, &berlin).unwrap(),
+                TimeSpan { start, end },
+                "{}",
+                value
+            );
+        }
+
+        // 02:30 is skipped in March and repeated in October, both resolve to an instant nearby
+        for (value, local) in [
+            ("2024-03-31 02:30", 1711852200),
+            ("2024-10-27 02:30", 1729996200),
+        ] {
+            let span = parse_time(value, &berlin).unwrap();
+            assert!(
+                [local - 3_600, local - 7_200].contains(&span.start),
+                "{}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_times() {
+        let utc = TimeZone::utc();
+        for value in [
+            "",
+            "2024",
+            "2024-03",
+            "2024-03-01-02",
+            "-0001-01-01",
+            "10000-01-01",
+            "2147483647-01-01",
+            "2024-00-10",
+            "2024-13-01",
+            "2024-02-30",
+            "2023-02-29",
+            "1900-02-29",
+            "2024-04-31",
+            "2024-03-00",
+            "2024-03-01T14",
+            "2024-03-01T24:00",
+            "2024-03-01T14:60",
+            "2024-03-01T14:30:60",
+            "2024-03-01T14:30:15:01",
+            "2024-03-01T14:30+24:00",
+            "2024-03-01T14:30 EST",
+            "2024-03-01T14:30+2",
+            "@",
+            "@x",
+            "@9223372036854775807",
+        ] {
+            assert!(
+                matches!(
+                    parse_time(value, &utc),
+                    Err(TreeGenError::InvalidDate { .. })
+                ),
+                "{:?} was parsed",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn parses_durations() {
+        let cases = [
+            ("90s", 90),
+            ("7d", 604_800),
+            ("2w", 1_209_600),
+            ("1h30m", 5_400),
+            ("1w2d", 777_600),
+            ("3 days", 259_200),
+            ("1 hour 5 mins", 3_900),
+        ];
+        for (value, seconds) in cases {
+            assert_eq!(parse_duration(value).unwrap(), seconds, "{}", value);
+        }
+        for value in ["", "7", "d", "7x", "1.5h", "9223372036854775807w"] {
+            assert!(parse_duration(value).is_err(), "{:?} was parsed", value);
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use std::{fmt, io, path::PathBuf};
+
+/// Errors raised while scanning, filtering or rendering a tree.
+#[derive(Debug)]
+pub enum TreeGenError {
+    /// A path given by the user could not be read.
+    Path { path: PathBuf, source: io::Error },
+    /// An include or exclude pattern is not a valid regex.
+    InvalidPattern {
+        pattern: String,
+        source: regex::Error,
+    },
+    /// A date or date filter could not be parsed.
+    InvalidDate { value: String, reason: String },
+    /// A file given by the user, like a snapshot, has invalid content.
+    Parse { path: PathBuf, reason: String },
+    /// The requested comparison cannot be made with the given trees.
+    Compare(String),
+    /// A tree could not be created on disk.
+    Restore { path: PathBuf, reason: String },
+    /// The tree could not be serialized into the output format.
+    Serialize(String),
+    /// Writing the output failed.
+    Io(io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, TreeGenError>;
+
+impl fmt::Display for TreeGenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TreeGenError::Path { path, source } => {
+                write!(f, "cannot read '{}': {}", path.display(), source)
+            }
+            TreeGenError::InvalidPattern { pattern, source } => {
+                write!(f, "invalid pattern '{}': {}", pattern, source)
+            }
+            TreeGenError::InvalidDate { value, reason } => {
+                write!(f, "invalid date '{}': {}", value, reason)
+            }
+            TreeGenError::Parse { path, reason } => {
+                write!(f, "cannot parse '{}': {}", path.display(), reason)
+            }
+            TreeGenError::Compare(reason) => write!(f, "cannot compare: {}", reason),
+            TreeGenError::Restore { path, reason } => {
+                write!(f, "cannot create '{}': {}", path.display(), reason)
+            }
+            TreeGenError::Serialize(message) => {
+                write!(f, "failed to serialize output: {}", message)
+            }
+            TreeGenError::Io(source) => write!(f, "failed to write output: {}", source),
+        }
+    }
+}
+
+impl std::error::Error for TreeGenError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TreeGenError::Path { source, .. } => Some(source),
+            TreeGenError::InvalidPattern { source, .. } => Some(source),
+            TreeGenError::Io(source) => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for TreeGenError {
+    fn from(err: io::Error) -> Self {
+        TreeGenError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for TreeGenError {
+    fn from(err: serde_json::Error) -> Self {
+        TreeGenError::Serialize(err.to_string())
+    }
+}
+
+impl From<serde_norway::Error> for TreeGenError {
+    fn from(err: serde_norway::Error) -> Self {
+        TreeGenError::Serialize(err.to_string())
+    }
+}
+
+impl From<toml::ser::Error> for TreeGenError {
+    fn from(err: toml::ser::Error) -> Self {
+        TreeGenError::Serialize(err.to_string())
+    }
+}
@@ -1,71 +1,383 @@
-use std::{
-    fs::{self, metadata},
-    path::Path,
-    time::UNIX_EPOCH,
+use std::time::SystemTime;
+
+use regex::Regex;
+
+use super::{
+    date::{parse_duration, parse_time, unix_timestamp, TimeSpan},
+    error::{Result, TreeGenError},
+    scan::{Node, ScanOptions},
+    timezone::TimeZone,
 };
 
-use super::date::parse_date;
-
-/// Check if a directory contains files with the specified extension (for filtering)
-pub fn contains_matching_files_extension(
-    path: &Path,
-    file_extension: Option<&String>,
-    ignore_hidden: bool,
-) -> bool {
-    if let Ok(entries) = fs::read_dir(path) {
-        for entry in entries.filter_map(Result::ok) {
-            let path = entry.path();
-            let file_name = path.file_name().unwrap().to_string_lossy();
-
-            // Ignore hidden files/folders if `ignore_hidden` is enabled
-            if ignore_hidden && file_name.starts_with('.') {
-                continue;
-            }
+/// Every form of `--date-filter`, for the error of an unknown one.
+const DATE_FILTER_FORMS: &str = "expected before, after, since, until or on <time>, \
+between <time> and <time>, newer-than or older-than <duration>, or an interval like \
+[2024-01-01, 2024-02-01)";
+
+/// A parsed `--date-filter` value: files modified from `start` up to but excluding `end`, in
+/// seconds since the UNIX epoch. A missing bound is open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateFilter {
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+}
+
+impl DateFilter {
+    /// Parse a filter relative to the current time, with times in the local time zone.
+    pub fn parse(filter: &str) -> Result<DateFilter> {
+        DateFilter::parse_at(
+            filter,
+            unix_timestamp(SystemTime::now()),
+            &TimeZone::local(),
+        )
+    }
+
+    /// Parse a filter relative to `now`, in seconds since the UNIX epoch, with times without
+    /// a zone in `zone`. The filter is one of:
+    ///
+    /// - `before <time>` or `after <time>`, excluding the time itself,
+    /// - `until <time>` or `since <time>`, including it,
+    /// - `on <time>`, only the time itself,
+    /// - `between <time> and <time>`, also written with a comma, including both,
+    /// - `newer-than <duration>` or `older-than <duration>`, like `7d` or `2w`,
+    /// - an interval like `[2024-01-01, 2024-02-01)`, where brackets include their time
+    ///   and parentheses exclude it.
+    ///
+    /// A date covers its whole day and a time the minute or second it is given to, so
+    /// `until 2024-01-31` includes the whole 31st and `after 2024-01-31` starts on February 1.
+    pub fn parse_at(filter: &str, now: i64, zone: &TimeZone) -> Result<DateFilter> {
+        let invalid = |reason: &str| TreeGenError::InvalidDate {
+            value: filter.trim().to_string(),
+            reason: reason.to_string(),
+        };
+        let filter_text = filter.trim();
 
-            // If it’s a file, check for extension match
-            if path.is_file() {
-                if let Some(ext) = file_extension {
-                    if path.extension().and_then(|e| e.to_str()) == Some(ext) {
-                        return true; // Found a matching file
-                    }
-                } else {
-                    return true; // No extension filter, any file counts
+        let (start, end) = if let Some(inner) = filter_text.strip_prefix(['[', '(']) {
+            let Some(inner) = inner.strip_suffix([']', ')']) else {
+                return Err(invalid("an interval must end with ] or )"));
+            };
+            let (from, to) = inner
+                .split_once(',')
+                .ok_or_else(|| invalid("expected two times separated by a comma"))?;
+            let (from, to) = (parse_time(from, zone)?, parse_time(to, zone)?);
+            let start = match filter_text.starts_with('[') {
+                true => from.start,
+                false => from.end,
+            };
+            let end = match filter_text.ends_with(']') {
+                true => to.end,
+                false => to.start,
+            };
+            (Some(start), Some(end))
+        } else {
+            let (keyword, value) = filter_text
+                .split_once(char::is_whitespace)
+                .map_or((filter_text, ""), |(keyword, value)| {
+                    (keyword, value.trim())
+                });
+            let missing = |what: &str| invalid(&format!("missing a {} after '{}'", what, keyword));
+            let time = || -> Result<TimeSpan> {
+                match value.is_empty() {
+                    true => Err(missing("time")),
+                    false => parse_time(value, zone),
+                }
+            };
+            let duration = || -> Result<i64> {
+                match value.is_empty() {
+                    true => Err(missing("duration")),
+                    false => parse_duration(value),
+                }
+            };
+            let ago = |duration: i64| {
+                now.checked_sub(duration)
+                    .ok_or_else(|| invalid("the duration is too long"))
+            };
+            match keyword {
+                "before" => (None, Some(time()?.start)),
+                "until" => (None, Some(time()?.end)),
+                "after" => (Some(time()?.end), None),
+                "since" => (Some(time()?.start), None),
+                "on" => {
+                    let span = time()?;
+                    (Some(span.start), Some(span.end))
+                }
+                "between" => {
+                    let (from, to) = split_range(value).ok_or_else(|| {
+                        invalid("expected between <time> and <time>, or <time>,<time>")
+                    })?;
+                    (
+                        Some(parse_time(from, zone)?.start),
+                        Some(parse_time(to, zone)?.end),
+                    )
                 }
+                "newer-than" => (Some(ago(duration()?)?), None),
+                "older-than" => (None, Some(ago(duration()?)?)),
+                _ => return Err(invalid(DATE_FILTER_FORMS)),
             }
+        };
 
-            // If it’s a directory, recursively check inside
-            if path.is_dir()
-                && contains_matching_files_extension(&path, file_extension, ignore_hidden)
-            {
-                return true; // Matching files found in a subdirectory
+        if let (Some(start), Some(end)) = (start, end) {
+            if start >= end {
+                return Err(invalid(
+                    "the range is empty, its end is not after its start",
+                ));
             }
         }
+        Ok(DateFilter { start, end })
     }
-    false // No matching files found
 }
 
-pub fn apply_date_filter(path: &Path, filter: &str) -> bool {
-    if let Ok(metadata) = metadata(path) {
-        if let Ok(modified) = metadata.modified() {
-            let modified_date = modified
-                .duration_since(UNIX_EPOCH)
-                .expect("Time went backwards")
-                .as_secs();
-
-            let filter_parts: Vec<&str> = filter.split_whitespace().collect();
-
-            match filter_parts.as_slice() {
-                ["before", filter_date] => modified_date < parse_date(filter_date),
-                ["after", filter_date] => modified_date > parse_date(filter_date),
-                ["between", date1, date2] => {
-                    modified_date > parse_date(date1) && modified_date < parse_date(date2)
-                }
-                _ => true,
-            }
-        } else {
-            true
+/// Split the two times of `between`, separated by `and`, a comma or, for dates, a space.
+fn split_range(value: &str) -> Option<(&str, &str)> {
+    if let Some(range) = value.split_once(" and ") {
+        return Some(range);
+    }
+    if let Some(range) = value.split_once(',') {
+        return Some(range);
+    }
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    match parts.as_slice() {
+        [from, to] => Some((from, to)),
+        _ => None,
+    }
+}
+
+/// Compile an include/exclude pattern. Patterns with `*` or `?` that are not a valid regex,
+/// like `*.tmp`, are treated as wildcards matching the whole file name.
+pub fn compile_pattern(pattern: &str) -> Result<Regex> {
+    Regex::new(pattern).or_else(|source| {
+        if !pattern.contains(['*', '?']) {
+            return Err(TreeGenError::InvalidPattern {
+                pattern: pattern.to_string(),
+                source,
+            });
+        }
+
+        let wildcard = regex::escape(pattern)
+            .replace(r"\*", ".*")
+            .replace(r"\?", ".");
+        Regex::new(&format!("^{}$", wildcard)).map_err(|_| TreeGenError::InvalidPattern {
+            pattern: pattern.to_string(),
+            source,
+        })
+    })
+}
+
+/// Compile several include/exclude patterns into one regex matching any of them.
+pub fn compile_patterns(patterns: &[String]) -> Result<Regex> {
+    let alternatives = patterns
+        .iter()
+        .map(|pattern| compile_pattern(pattern).map(|regex| format!("(?:{})", regex.as_str())))
+        .collect::<Result<Vec<_>>>()?;
+    // Every alternative is a valid regex on its own, so the combination is too
+    Ok(Regex::new(&alternatives.join("|")).unwrap())
+}
+
+/// Check a scanned file against the extension, size, include/exclude and date filters.
+pub fn matches_file_filters(node: &Node, options: &ScanOptions) -> bool {
+    // Filter by file extension if provided
+    if let Some(ext) = &options.file_extension {
+        if node.path.extension().and_then(|e| e.to_str()) != Some(ext.as_str()) {
+            return false;
+        }
+    }
+
+    // Filter by file size
+    if options.size_min.is_some_and(|min| node.size < min) {
+        return false;
+    }
+    if options.size_max.is_some_and(|max| node.size > max) {
+        return false;
+    }
+
+    // Filter by include/exclude patterns if provided
+    if let Some(include) = &options.include {
+        if !include.is_match(&node.name) {
+            return false;
+        }
+    }
+    if let Some(exclude) = &options.exclude {
+        if exclude.is_match(&node.name) {
+            return false;
+        }
+    }
+
+    // Filter by date if provided, files without the selected timestamp never match
+    if let Some(date_filter) = options.date_filter {
+        return options
+            .time_field
+            .of(node)
+            .is_some_and(|time| apply_date_filter(time, date_filter));
+    }
+
+    true
+}
+
+/// Check an entry of a directory against the filters. Hidden entries and excluded directories
+/// are dropped with everything inside them, and files must pass [`matches_file_filters`].
+/// When filtering by extension, the walk also drops directories without a matching file.
+pub fn matches_entry_filters(node: &Node, options: &ScanOptions) -> bool {
+    if options.ignore_hidden && node.name.starts_with('.') {
+        return false;
+    }
+    match node.is_dir() {
+        true => !is_excluded(node, options),
+        false => matches_file_filters(node, options),
+    }
+}
+
+/// Check whether the name of an entry matches the exclude pattern.
+pub fn is_excluded(node: &Node, options: &ScanOptions) -> bool {
+    options
+        .exclude
+        .as_ref()
+        .is_some_and(|exclude| exclude.is_match(&node.name))
+}
+
+pub fn apply_date_filter(time: SystemTime, filter: DateFilter) -> bool {
+    let time = unix_timestamp(time);
+    filter.start.is_none_or(|start| time >= start) && filter.end.is_none_or(|end| time < end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date::from_unix_timestamp;
+
+    /// 2024-03-01 11:06:40 UTC.
+    const NOW: i64 = 1709291200;
+    const MARCH_1: i64 = 1709251200;
+    const MARCH_2: i64 = 1709337600;
+    const MARCH_3: i64 = 1709424000;
+
+    fn parse(filter: &str) -> Result<(Option<i64>, Option<i64>)> {
+        DateFilter::parse_at(filter, NOW, &TimeZone::utc()).map(|filter| (filter.start, filter.end))
+    }
+
+    #[test]
+    fn parses_every_form() {
+        let cases = [
+            ("before 2024-03-01", (None, Some(MARCH_1))),
+            ("until 2024-03-01", (None, Some(MARCH_2))),
+            ("after 2024-03-01", (Some(MARCH_2), None)),
+            ("since 2024-03-01", (Some(MARCH_1), None)),
+            ("  since   2024-03-01 ", (Some(MARCH_1), None)),
+            ("on 2024-03-01", (Some(MARCH_1), Some(MARCH_2))),
+            ("on 2024-03-01 14:30", (Some(1709303400), Some(1709303460))),
+            ("on @1709303400", (Some(1709303400), Some(1709303401))),
+            ("until 2024-03-01 14:30", (None, Some(1709303460))),
+            (
+                "between 2024-03-01 and 2024-03-02",
+                (Some(MARCH_1), Some(MARCH_3)),
+            ),
+            (
+                "between 2024-03-01,2024-03-02",
+                (Some(MARCH_1), Some(MARCH_3)),
+            ),
+            (
+                "between 2024-03-01, 2024-03-02",
+                (Some(MARCH_1), Some(MARCH_3)),
+            ),
+            (
+                "between 2024-03-01 2024-03-02",
+                (Some(MARCH_1), Some(MARCH_3)),
+            ),
+            (
+                "between 2024-03-01 08:00 and 2024-03-01 18:00",
+                (Some(1709280000), Some(1709316060)),
+            ),
+            ("newer-than 7d", (Some(NOW - 604_800), None)),
+            ("older-than 1h30m", (None, Some(NOW - 5_400))),
+        ];
+        for (filter, range) in cases {
+            assert_eq!(parse(filter).unwrap(), range, "{}", filter);
+        }
+    }
+
+    #[test]
+    fn parses_every_interval_bracket() {
+        let cases = [
+            ("[2024-03-01, 2024-03-02]", (MARCH_1, MARCH_3)),
+            ("[2024-03-01, 2024-03-02)", (MARCH_1, MARCH_2)),
+            ("(2024-03-01, 2024-03-02]", (MARCH_2, MARCH_3)),
+            ("(2024-03-01, 2024-03-03)", (MARCH_2, MARCH_3)),
+            ("[2024-03-01,2024-03-01]", (MARCH_1, MARCH_2)),
+            ("[@0, @10)", (0, 10)),
+            ("(@0, @10]", (1, 11)),
+        ];
+        for (filter, (start, end)) in cases {
+            assert_eq!(
+                parse(filter).unwrap(),
+                (Some(start), Some(end)),
+                "{}",
+                filter
+            );
+        }
+    }
+
+    #[test]
+    fn uses_the_local_zone() {
+        let berlin = TimeZone::from_tz("CET-1CEST,M3.5.0,M10.5.0/3").unwrap();
+        let filter = DateFilter::parse_at("on 2024-03-31", NOW, &berlin).unwrap();
+        // The day the clocks go forward lasts 23 hours
+        assert_eq!(
+            (filter.start, filter.end),
+            (Some(1711839600), Some(1711922400))
+        );
+        let filter = DateFilter::parse_at("since 2024-03-01 Z", NOW, &berlin).unwrap();
+        assert_eq!(filter.start, Some(MARCH_1));
+    }
+
+    #[test]
+    fn rejects_invalid_filters() {
+        for filter in [
+            "",
+            "2024-03-01",
+            "yesterday",
+            "before",
+            "on",
+            "newer-than",
+            "newer-than 7",
+            "older-than 7x",
+            "on 2024-02-30",
+            "on 2023-02-29",
+            "after 2147483647-01-01",
+            "on @9223372036854775807",
+            "between 2024-03-01",
+            "between 2024-03-02 and 2024-03-01",
+            "between 2024-03-01 and 2024-03-01 and 2024-03-02",
+            "[2024-03-01, 2024-03-02",
+            "[2024-03-01]",
+            "[2024-03-02, 2024-03-01]",
+            "(2024-03-01, 2024-03-02)",
+            "(@0, @1)",
+        ] {
+            assert!(
+                matches!(parse(filter), Err(TreeGenError::InvalidDate { .. })),
+                "{:?} was parsed",
+                filter
+            );
+        }
+    }
+
+    #[test]
+    fn applies_half_open_ranges() {
+        let filter = DateFilter {
+            start: Some(10),
+            end: Some(20),
+        };
+        for (secs, matches) in [(9, false), (10, true), (19, true), (20, false), (-5, false)] {
+            assert_eq!(
+                apply_date_filter(from_unix_timestamp(secs), filter),
+                matches,
+                "{}",
+                secs
+            );
         }
-    } else {
-        true
+        let open = DateFilter {
+            start: None,
+            end: None,
+        };
+        assert!(apply_date_filter(from_unix_timestamp(i64::MIN / 2), open));
     }
 }
@@ -3,7 +3,8 @@ use std::path::Path;
 pub fn get_file_icon(path: &Path) -> char {
     if path.is_file() {
         if let Some(ext) = path.extension() {
-            match ext.to_str().unwrap() {
+            // Extensions that are not valid UTF-8 get the default file icon
+            match ext.to_str().unwrap_or_default() {
                 "ai" => '\u{e7b4}',
                 "android" => '\u{e70e}',
                 "apk" => '\u{e70e}',
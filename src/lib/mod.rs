@@ -1,6 +1,21 @@
+//! Generate and render directory structures.
+//!
+//! Scan a directory once with [`TreeGen::builder`] and render the resulting tree with any
+//! [`render::Renderer`], the same way the `tree_gen` CLI does.
+
+pub mod builder;
 pub mod compare;
 pub mod date;
+pub mod error;
 pub mod filter;
-pub mod generate;
 pub mod icon;
 pub mod output_file;
+pub mod parse;
+pub mod render;
+pub mod restore;
+pub mod scaffold;
+pub mod scan;
+pub mod timezone;
+
+pub use builder::{TreeGen, TreeGenBuilder};
+pub use error::TreeGenError;
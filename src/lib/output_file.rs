@@ -1,14 +1,10 @@
-use std::fs::OpenOptions;
-use std::io::{self, Write};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
 
-pub fn write_output(output_file: &str, content: &str) -> io::Result<()> {
-    let mut file = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(output_file)?;
-
-    // Write Markdown header and code block syntax
-    writeln!(file, "{}", content)?;
-
-    Ok(())
+/// Open the destination of the rendered output, a file when given or the terminal otherwise.
+pub fn open_output(output_file: Option<&String>) -> io::Result<Box<dyn Write>> {
+    match output_file {
+        Some(output_file) => Ok(Box::new(BufWriter::new(File::create(output_file)?))),
+        None => Ok(Box::new(BufWriter::new(io::stdout().lock()))),
+    }
 }
@@ -0,0 +1,297 @@
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+
+use super::{
+    error::{Result, TreeGenError},
+    scan::{Node, NodeKind},
+};
+
+/// Branches that start an entry, in both branch styles.
+const BRANCHES: [&str; 4] = ["├── ", "└── ", "|-- ", "`-- "];
+
+/// Indentation drawn for each level above an entry, in both branch styles.
+const CONTINUATIONS: [&str; 3] = ["│   ", "|   ", "    "];
+
+/// Marker the ascii output writes after unreadable directories.
+const ERROR_MARKER: &str = " [error opening dir: ";
+
+/// An entry line of a text tree, with the decorations of the ascii output taken apart.
+struct TextEntry {
+    depth: usize,
+    name: String,
+    size: Option<u64>,
+    link_target: Option<PathBuf>,
+    error: Option<String>,
+}
+
+/// Parse a text tree, like the ascii output writes, back into a tree of nodes.
+///
+/// The first line is the root, unless it already starts with a branch. Entries with children,
+/// a name ending in `/` or an error are directories, entries with a `-> target` are symlinks
+/// and everything else is a file. Colors, `--icons` glyphs and `--show-sizes` suffixes are
+/// understood, sizes are only known to the 0.01 KB the output shows. Paths are relative to
+/// the root and all other metadata is left empty. `source` is only used to report errors.
+pub fn parse_tree(text: &str, source: &Path) -> Result<Node> {
+    let colors = Regex::new(r"\x1b\[[0-9;]*m").unwrap();
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, colors.replace_all(line, "").replace('\u{a0}', " ")))
+        // Lines with only continuations are spacing between entries
+        .filter(|(_, line)| !line.trim_matches(['│', '|', ' ', '\t']).is_empty())
+        .peekable();
+
+    let root_name = match lines.peek() {
+        Some((_, line)) if split_branch(line).is_none() => {
+            let name = line.trim().trim_end_matches('/').to_string();
+            lines.next();
+            name
+        }
+        _ => ".".to_string(),
+    };
+
+    let mut entries = vec![];
+    let mut previous_depth = 0;
+    for (number, line) in lines {
+        let Some((depth, text)) = split_branch(&line) else {
+            return Err(parse_error(source, number, "expected a tree branch"));
+        };
+        if depth > previous_depth + 1 {
+            return Err(parse_error(source, number, "entry is indented too deep"));
+        }
+        let entry = parse_entry(depth, text);
+        if entry.name.is_empty() || entry.name == "/" {
+            return Err(parse_error(source, number, "entry has no name"));
+        }
+        entries.push(entry);
+        previous_depth = depth;
+    }
+
+    let mut root = text_node(PathBuf::from(&root_name), root_name, NodeKind::Directory);
+    let mut rest = entries.as_slice();
+    root.children = build_children(&root.path, 1, &mut rest);
+    Ok(root)
+}
+
+/// Split a line into the depth of its entry, where the root's children are at depth 1,
+/// and the text after the branch.
+fn split_branch(line: &str) -> Option<(usize, &str)> {
+    let mut rest = line;
+    let mut depth = 1;
+    loop {
+        if let Some(text) = BRANCHES.iter().find_map(|branch| rest.strip_prefix(branch)) {
+            return Some((depth, text.trim()));
+        }
+        rest = CONTINUATIONS
+            .iter()
+            .find_map(|continuation| rest.strip_prefix(continuation))?;
+        depth += 1;
+    }
+}
+
+/// Take the decorations off an entry, in the reverse order the ascii output adds them.
+fn parse_entry(depth: usize, text: &str) -> TextEntry {
+    let mut text = text;
+
+    let mut error = None;
+    if let Some(start) = text.find(ERROR_MARKER) {
+        if text.ends_with(']') {
+            error = Some(text[start + 2..text.len() - 1].to_string());
+            text = &text[..start];
+        }
+    }
+
+    let mut link_target = None;
+    if let Some((name, target)) = text.split_once(" -> ") {
+        link_target = Some(PathBuf::from(target));
+        text = name;
+    }
+
+    let mut size = None;
+    if let Some((name, kilobytes)) = text
+        .strip_suffix(" KB)")
+        .and_then(|rest| rest.rsplit_once(" ("))
+    {
+        if let Ok(kilobytes) = kilobytes.parse::<f64>() {
+            size = Some((kilobytes * 1024.0).round() as u64);
+            text = name;
+        }
+    }
+
+    // Every icon is a Nerd Font glyph from a private use area, followed by a space
+    let mut chars = text.chars();
+    if let (Some(icon), Some(' ')) = (chars.next(), chars.next()) {
+        if matches!(icon, '\u{e000}'..='\u{f8ff}' | '\u{f0000}'..='\u{ffffd}') {
+            text = chars.as_str();
+        }
+    }
+
+    TextEntry {
+        depth,
+        name: text.to_string(),
+        size,
+        link_target,
+        error,
+    }
+}
+
+/// Build the nodes at `depth` from the front of `entries`, with their children.
+fn build_children(parent: &Path, depth: usize, entries: &mut &[TextEntry]) -> Vec<Node> {
+    let mut children = vec![];
+    while let Some((entry, rest)) = entries.split_first() {
+        if entry.depth < depth {
+            break;
+        }
+        *entries = rest;
+
+        let has_children = entries.first().is_some_and(|next| next.depth > depth);
+        let kind = if entry.link_target.is_some() {
+            NodeKind::Symlink
+        } else if has_children || entry.error.is_some() || entry.name.ends_with('/') {
+            NodeKind::Directory
+        } else {
+            NodeKind::File
+        };
+        let name = entry.name.trim_end_matches('/').to_string();
+
+        let mut node = text_node(parent.join(&name), name, kind);
+        node.size = entry.size.unwrap_or(0);
+        node.link_target = entry.link_target.clone();
+        node.error = entry.error.clone();
+        node.children = build_children(&node.path, depth + 1, entries);
+        children.push(node);
+    }
+    children
+}
+
+fn text_node(path: PathBuf, name: String, kind: NodeKind) -> Node {
+    Node {
+        name,
+        path,
+        kind,
+        size: 0,
+        mode: None,
+        modified: None,
+        accessed: None,
+        changed: None,
+        created: None,
+        uid: None,
+        gid: None,
+        inode: None,
+        nlink: None,
+        link_target: None,
+        error: None,
+        children: vec![],
+    }
+}
+
+fn parse_error(source: &Path, line: usize, reason: &str) -> TreeGenError {
+    TreeGenError::Parse {
+        path: source.to_path_buf(),
+        reason: format!("line {}: {}", line, reason),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<Node> {
+        parse_tree(text, Path::new("tree.txt"))
+    }
+
+    /// Every entry below the root as its path, kind and size, parents first.
+    fn entries(node: &Node) -> Vec<(String, NodeKind, u64)> {
+        node.children
+            .iter()
+            .flat_map(|child| {
+                let entry = (child.path.display().to_string(), child.kind, child.size);
+                std::iter::once(entry).chain(entries(child))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parses_both_branch_styles() {
+        let unicode = "root\n├── src\n│   ├── lib.rs\n│   └── main.rs\n│\n└── docs/\n";
+        let ascii = "root\n|-- src\n|   |-- lib.rs\n|   `-- main.rs\n|\n`-- docs/\n";
+        let expected = vec![
+            ("root/src".to_string(), NodeKind::Directory, 0),
+            ("root/src/lib.rs".to_string(), NodeKind::File, 0),
+            ("root/src/main.rs".to_string(), NodeKind::File, 0),
+            ("root/docs".to_string(), NodeKind::Directory, 0),
+        ];
+        for text in [unicode, ascii] {
+            let tree = parse(text).unwrap();
+            assert_eq!(tree.name, "root");
+            assert_eq!(entries(&tree), expected, "{}", text);
+        }
+    }
+
+    #[test]
+    fn strips_icons_sizes_and_colors() {
+        let text = "root\n├── \u{f07b} src (0.01 KB)\n│   └── \u{e7a8} main.rs (1.50 KB)\n└── \x1b[32mREADME.md\x1b[0m (12.00 KB)\n";
+        assert_eq!(
+            entries(&parse(text).unwrap()),
+            vec![
+                ("root/src".to_string(), NodeKind::Directory, 10),
+                ("root/src/main.rs".to_string(), NodeKind::File, 1536),
+                ("root/README.md".to_string(), NodeKind::File, 12288),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_symlink_targets() {
+        let tree = parse("root\n├── lib -> ../shared/lib\n└── a (0.01 KB) -> b\n").unwrap();
+        let link = &tree.children[0];
+        assert_eq!((link.name.as_str(), link.kind), ("lib", NodeKind::Symlink));
+        assert_eq!(
+            link.link_target.as_deref(),
+            Some(Path::new("../shared/lib"))
+        );
+        // The size suffix comes after the target, like the ascii output writes it
+        assert_eq!(
+            tree.children[1].link_target.as_deref(),
+            Some(Path::new("b"))
+        );
+    }
+
+    #[test]
+    fn parses_unreadable_directories() {
+        let tree = parse("root\n└── locked [error opening dir: Permission denied (os error 13)]\n")
+            .unwrap();
+        let locked = &tree.children[0];
+        assert_eq!(
+            (locked.name.as_str(), locked.kind),
+            ("locked", NodeKind::Directory)
+        );
+        assert_eq!(
+            locked.error.as_deref(),
+            Some("error opening dir: Permission denied (os error 13)")
+        );
+    }
+
+    #[test]
+    fn refuses_badly_indented_lines() {
+        let cases = [
+            (
+                "root\n├── a\n│   │   └── b\n",
+                "line 3: entry is indented too deep",
+            ),
+            ("root\n├── a\nb\n", "line 3: expected a tree branch"),
+            ("root\n└── /\n", "line 2: entry has no name"),
+        ];
+        for (text, expected) in cases {
+            match parse(text) {
+                Err(TreeGenError::Parse { reason, .. }) => assert_eq!(reason, expected),
+                other => panic!(
+                    "{:?} parsed as {:?}",
+                    text,
+                    other.map(|tree| entries(&tree))
+                ),
+            }
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, Write},
+};
+
+use colored::Colorize;
+
+use super::{BranchStyle, RenderOptions, Renderer};
+use crate::{error::Result, icon::get_file_icon, scan::Node};
+
+/// Draws the tree with ASCII or unicode branches, like the `tree` command.
+pub struct AsciiRenderer {
+    branch_style: BranchStyle,
+    show_size: bool,
+    icons: bool,
+    preview_lines: Option<usize>,
+    color: bool,
+}
+
+impl AsciiRenderer {
+    pub fn new(options: &RenderOptions) -> Self {
+        AsciiRenderer {
+            branch_style: options.branch_style,
+            show_size: options.show_size,
+            icons: options.icons,
+            preview_lines: options.preview_lines,
+            color: options.color,
+        }
+    }
+
+    /// Write the children of a scanned directory, one line per entry.
+    fn render_children(&self, node: &Node, prefix: &str, out: &mut dyn Write) -> Result<()> {
+        let (branch, last_branch, continuation) = self.branch_style.prefixes();
+
+        for (i, child) in node.children.iter().enumerate() {
+            let is_last = i == node.children.len() - 1;
+            let new_prefix = if is_last { last_branch } else { branch };
+
+            writeln!(out, "{}{}{}", prefix, new_prefix, self.display_name(child))?;
+
+            // If preview lines flag parse and current node is a file, then show preview content of file
+            if child.is_file() {
+                if let Some(num_lines) = self.preview_lines {
+                    let preview_prefix = if is_last {
+                        format!("{}     ", prefix)
+                    } else {
+                        format!("{}|    ", prefix)
+                    };
+                    self.render_preview(child, &preview_prefix, num_lines, out)?;
+                }
+            }
+
+            // If node is dir, then recurse into directories
+            if child.is_dir() {
+                let additional_prefix = if is_last { "    " } else { continuation };
+                self.render_children(child, &format!("{}{}", prefix, additional_prefix), out)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the name of an entry together with its optional icon and size.
+    fn display_name(&self, node: &Node) -> String {
+        // Use color for better visualization
+        let name = match (self.color, node.is_dir()) {
+            (true, true) => node.name.green().to_string(),
+            _ => node.name.clone(),
+        };
+
+        // Combine icon and file name
+        let name = match self.icons {
+            true => format!("{} {}", get_file_icon(&node.path), name),
+            false => name,
+        };
+
+        // Convert to KB by divided by 1024
+        let name = match self.show_size {
+            true => format!("{} ({:.2} KB)", name, node.size as f64 / 1024.0),
+            false => name,
+        };
+
+        // Show where symlinks point to, like `tree` does
+        let name = match &node.link_target {
+            Some(target) => format!("{} -> {}", name, target.display()),
+            None => name,
+        };
+
+        // Report unreadable directories inline, like `tree` does
+        match &node.error {
+            Some(error) => format!("{} [{}]", name, error),
+            None => name,
+        }
+    }
+
+    /// Write the first `num_lines` lines of a file below its entry.
+    fn render_preview(
+        &self,
+        node: &Node,
+        preview_prefix: &str,
+        num_lines: usize,
+        out: &mut dyn Write,
+    ) -> Result<()> {
+        if let Ok(file) = File::open(&node.path) {
+            let reader = io::BufReader::new(file);
+            for line in reader.lines().take(num_lines) {
+                match line {
+                    Ok(content) => writeln!(out, "{}{}", preview_prefix, content)?,
+                    Err(_) => {
+                        writeln!(
+                            out,
+                            "{}Cannot display preview: non-UTF-8 content",
+                            preview_prefix
+                        )?;
+                        break;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Renderer for AsciiRenderer {
+    fn render(&self, tree: &Node, out: &mut dyn Write) -> Result<()> {
+        let root_name = match self.color {
+            true => tree.name.green().to_string(),
+            false => tree.name.clone(),
+        };
+        writeln!(out, "{}", root_name)?;
+        self.render_children(tree, "", out)
+    }
+
+    fn directory_sizes(&self) -> bool {
+        self.show_size
+    }
+}
@@ -0,0 +1,166 @@
+use std::{io::Write, time::SystemTime};
+
+use super::{RenderOptions, Renderer};
+use crate::{date::TimeFormat, error::Result, scan::Node};
+
+/// A column of the flat CSV/TSV listing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Path,
+    Depth,
+    Type,
+    Size,
+    Permission,
+    Modified,
+    Accessed,
+    Changed,
+    Created,
+}
+
+impl Column {
+    /// Every column, in the default order.
+    pub const ALL: [Column; 9] = [
+        Column::Path,
+        Column::Depth,
+        Column::Type,
+        Column::Size,
+        Column::Permission,
+        Column::Modified,
+        Column::Accessed,
+        Column::Changed,
+        Column::Created,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Column::Path => "path",
+            Column::Depth => "depth",
+            Column::Type => "type",
+            Column::Size => "size",
+            Column::Permission => "permission",
+            Column::Modified => "modified",
+            Column::Accessed => "accessed",
+            Column::Changed => "changed",
+            Column::Created => "created",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Column> {
+        Column::ALL.into_iter().find(|column| column.name() == name)
+    }
+
+    /// Get the value of this column for an entry.
+    fn value(&self, root: &Node, node: &Node, depth: usize, time_format: &TimeFormat) -> String {
+        match self {
+            Column::Path => node
+                .path
+                .strip_prefix(&root.path)
+                .unwrap_or(&node.path)
+                .to_string_lossy()
+                .into_owned(),
+            Column::Depth => depth.to_string(),
+            Column::Type => node.kind.name().to_string(),
+            Column::Size => node.size.to_string(),
+            Column::Permission => node
+                .mode
+                .map(|mode| format!("{:o}", mode))
+                .unwrap_or_default(),
+            Column::Modified => format_time(node.modified, time_format),
+            Column::Accessed => format_time(node.accessed, time_format),
+            Column::Changed => format_time(node.changed, time_format),
+            Column::Created => format_time(node.created, time_format),
+        }
+    }
+}
+
+/// Format an optional timestamp, empty when the entry does not have it.
+fn format_time(time: Option<SystemTime>, time_format: &TimeFormat) -> String {
+    time.map(|time| time_format.format(time))
+        .unwrap_or_default()
+}
+
+/// Writes one row per entry below the root, separated by commas (CSV) or tabs (TSV).
+pub struct DelimitedRenderer {
+    delimiter: char,
+    columns: Vec<Column>,
+    time_format: TimeFormat,
+}
+
+impl DelimitedRenderer {
+    pub fn csv(options: &RenderOptions) -> Self {
+        DelimitedRenderer::new(',', options)
+    }
+
+    pub fn tsv(options: &RenderOptions) -> Self {
+        DelimitedRenderer::new('\t', options)
+    }
+
+    fn new(delimiter: char, options: &RenderOptions) -> Self {
+        DelimitedRenderer {
+            delimiter,
+            columns: match options.columns.is_empty() {
+                true => Column::ALL.to_vec(),
+                false => options.columns.clone(),
+            },
+            time_format: options.time_format.clone(),
+        }
+    }
+
+    fn render_rows(
+        &self,
+        root: &Node,
+        node: &Node,
+        depth: usize,
+        out: &mut dyn Write,
+    ) -> Result<()> {
+        for child in &node.children {
+            let row: Vec<String> = self
+                .columns
+                .iter()
+                .map(|column| column.value(root, child, depth, &self.time_format))
+                .collect();
+            self.write_row(&row, out)?;
+            self.render_rows(root, child, depth + 1, out)?;
+        }
+        Ok(())
+    }
+
+    fn write_row(&self, row: &[String], out: &mut dyn Write) -> Result<()> {
+        let fields: Vec<String> = row.iter().map(|field| self.escape(field)).collect();
+        writeln!(out, "{}", fields.join(&self.delimiter.to_string()))?;
+        Ok(())
+    }
+
+    /// Quote CSV fields that need it, TSV has no quoting so tabs and newlines are escaped instead.
+    fn escape(&self, field: &str) -> String {
+        if self.delimiter == '\t' {
+            return field
+                .replace('\\', "\\\\")
+                .replace('\t', "\\t")
+                .replace('\n', "\\n")
+                .replace('\r', "\\r");
+        }
+
+        if field.contains([self.delimiter, '"', '\n', '\r']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_string()
+        }
+    }
+}
+
+impl Renderer for DelimitedRenderer {
+    fn render(&self, tree: &Node, out: &mut dyn Write) -> Result<()> {
+        let header: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| column.name().to_string())
+            .collect();
+        self.write_row(&header, out)?;
+        self.render_rows(tree, tree, 1, out)
+    }
+
+    fn directory_sizes(&self) -> bool {
+        self.columns.contains(&Column::Size)
+    }
+}
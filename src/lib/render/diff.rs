@@ -0,0 +1,718 @@
+use std::{io::Write, path::Path};
+
+use colored::{Color, Colorize};
+use serde::Serialize;
+
+use super::{BranchStyle, DiffRenderer, MergeRenderer, RenderOptions};
+use crate::{
+    compare::{Attribute, Change, DiffNode, DiffSummary, MergeChange, MergeNode},
+    date::{datetime_to_iso8601, unix_timestamp},
+    error::Result,
+    scan::Node,
+};
+
+/// Marker and color of each change, shared by the text outputs.
+fn change_style(change: Change) -> (&'static str, Color) {
+    match change {
+        Change::Unchanged => ("=", Color::Green),
+        Change::Added => ("+", Color::Green),
+        Change::Removed => ("-", Color::Red),
+        Change::Modified => ("~", Color::Yellow),
+        Change::TypeChanged => ("!", Color::Red),
+        Change::Moved => (">", Color::Blue),
+    }
+}
+
+fn paint(text: &str, color: Color, enabled: bool) -> String {
+    match enabled {
+        true => text.color(color).to_string(),
+        false => text.to_string(),
+    }
+}
+
+/// Get the compared attributes that differ on an entry, like ` [mode 644→755, owner 0→1000]`.
+fn attribute_changes(diff: &DiffNode) -> String {
+    if diff.changed_attributes.is_empty() {
+        return String::new();
+    }
+    let value = |node: &Option<Node>, attribute: &Attribute| {
+        node.as_ref()
+            .and_then(|node| attribute.value(node))
+            .unwrap_or_default()
+    };
+    let changes: Vec<String> = diff
+        .changed_attributes
+        .iter()
+        .map(|attribute| {
+            format!(
+                "{} {}→{}",
+                attribute.name(),
+                value(&diff.left, attribute),
+                value(&diff.right, attribute)
+            )
+        })
+        .collect();
+    format!(" [{}]", changes.join(", "))
+}
+
+fn root_label(node: Option<&Node>) -> String {
+    node.map(|node| node.path.display().to_string())
+        .unwrap_or_default()
+}
+
+/// Lists the differences as an indented outline, one marker per entry.
+pub struct TextDiffRenderer {
+    color: bool,
+}
+
+impl TextDiffRenderer {
+    pub fn new(options: &RenderOptions) -> Self {
+        TextDiffRenderer {
+            color: options.color,
+        }
+    }
+
+    fn render_children(&self, diff: &DiffNode, indent: &str, out: &mut dyn Write) -> Result<()> {
+        for child in &diff.children {
+            let name = child.name.as_str();
+            match child.change {
+                // Directories on both sides are listed with their compared contents below
+                Change::Unchanged | Change::Modified
+                    if child.left.as_ref().is_some_and(Node::is_dir) =>
+                {
+                    writeln!(
+                        out,
+                        "{}{}{}",
+                        indent,
+                        paint(name, Color::Cyan, self.color),
+                        attribute_changes(child)
+                    )?;
+                    // Report unreadable subdirectories inline and keep comparing
+                    if let Some(error) = &child.error {
+                        writeln!(out, "{}  [{}]", indent, error)?;
+                    }
+                    self.render_children(child, &format!("{}  ", indent), out)?;
+                }
+                Change::Modified => {
+                    let (left, right) = (child.left.as_ref(), child.right.as_ref());
+                    write!(
+                        out,
+                        "{}~ {}",
+                        indent,
+                        paint(name, Color::Yellow, self.color)
+                    )?;
+                    if child.contents_changed {
+                        write!(
+                            out,
+                            " ({} bytes in {}, {} bytes in {})",
+                            left.map_or(0, |node| node.size),
+                            parent_label(left),
+                            right.map_or(0, |node| node.size),
+                            parent_label(right)
+                        )?;
+                    }
+                    write!(out, "{}", attribute_changes(child))?;
+                    match &child.error {
+                        Some(error) => writeln!(out, " [{}]", error)?,
+                        None => writeln!(out)?,
+                    }
+                }
+                Change::TypeChanged => {
+                    let (left, right) = (child.left.as_ref(), child.right.as_ref());
+                    writeln!(
+                        out,
+                        "{}{} ({} in {}, {} in {})",
+                        indent,
+                        paint(name, Color::Red, self.color),
+                        left.map_or("", |node| node.kind.name()),
+                        parent_label(left),
+                        right.map_or("", |node| node.kind.name()),
+                        parent_label(right)
+                    )?;
+                }
+                Change::Moved => {
+                    let (verb, from) = moved_from(child);
+                    writeln!(
+                        out,
+                        "{}> {} ({} from {})",
+                        indent,
+                        paint(name, Color::Blue, self.color),
+                        verb,
+                        from
+                    )?;
+                }
+                change => {
+                    let (marker, color) = change_style(change);
+                    writeln!(
+                        out,
+                        "{}{} {}",
+                        indent,
+                        marker,
+                        paint(name, color, self.color)
+                    )?;
+                    // Directories only on one side are listed on their own, unless files moved in or out
+                    if contains_moves(child) {
+                        self.render_children(child, &format!("{}  ", indent), out)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Get whether a file was renamed or moved, and the path it comes from.
+/// A file that stays in the same directory was only renamed.
+fn moved_from(diff: &DiffNode) -> (&'static str, String) {
+    let from = diff.moved_from.as_deref().unwrap_or(Path::new(""));
+    match from.parent() == diff.path.parent() {
+        true => (
+            "renamed",
+            from.file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+        ),
+        false => ("moved", from.display().to_string()),
+    }
+}
+
+fn contains_moves(diff: &DiffNode) -> bool {
+    diff.children
+        .iter()
+        .any(|child| child.change == Change::Moved || contains_moves(child))
+}
+
+fn parent_label(node: Option<&Node>) -> String {
+    node.and_then(|node| node.path.parent())
+        .map(|parent| parent.display().to_string())
+        .unwrap_or_default()
+}
+
+impl DiffRenderer for TextDiffRenderer {
+    fn render(&self, diff: &DiffNode, out: &mut dyn Write) -> Result<()> {
+        writeln!(
+            out,
+            "Comparing {} vs {}",
+            root_label(diff.left.as_ref()),
+            root_label(diff.right.as_ref())
+        )?;
+        if let Some(error) = &diff.error {
+            writeln!(out, "[{}]", error)?;
+        }
+        self.render_children(diff, "", out)?;
+        writeln!(out, "{}", diff.summary())?;
+        Ok(())
+    }
+}
+
+/// Draws both trees next to each other in one unified tree, with a change marker between them.
+pub struct SideBySideRenderer {
+    branch_style: BranchStyle,
+    color: bool,
+}
+
+/// A line of the side-by-side output.
+struct Row {
+    left: String,
+    marker: Option<(&'static str, Color)>,
+    right: String,
+}
+
+impl SideBySideRenderer {
+    pub fn new(options: &RenderOptions) -> Self {
+        SideBySideRenderer {
+            branch_style: options.branch_style,
+            color: options.color,
+        }
+    }
+
+    fn collect_rows(&self, diff: &DiffNode, prefix: &str, rows: &mut Vec<Row>) {
+        let (branch, last_branch, continuation) = self.branch_style.prefixes();
+
+        for (i, child) in diff.children.iter().enumerate() {
+            let is_last = i == diff.children.len() - 1;
+            let new_prefix = if is_last { last_branch } else { branch };
+
+            // A side without the entry keeps drawing the branches of its parents
+            let side = |node: Option<&Node>| match node {
+                Some(node) => format!("{}{}{}", prefix, new_prefix, entry_label(node)),
+                None => prefix.trim_end().to_string(),
+            };
+            // Moved files show where they come from on the left
+            let left = match child.change {
+                Change::Moved => format!("{}{}{}", prefix, new_prefix, moved_from(child).1),
+                _ => side(child.left.as_ref()),
+            };
+            let is_dir = child.left.as_ref().is_some_and(Node::is_dir)
+                && child.right.as_ref().is_some_and(Node::is_dir);
+            rows.push(Row {
+                left,
+                // Directories on both sides show their changes on their entries instead
+                marker: match (child.change, is_dir) {
+                    (Change::Unchanged, true) => None,
+                    (change, _) => Some(change_style(change)),
+                },
+                right: format!("{}{}", side(child.right.as_ref()), attribute_changes(child)),
+            });
+
+            let additional_prefix = if is_last { "    " } else { continuation };
+            self.collect_rows(child, &format!("{}{}", prefix, additional_prefix), rows);
+        }
+    }
+}
+
+/// Get the name of an entry with where it points to or why it could not be read.
+fn entry_label(node: &Node) -> String {
+    let name = match &node.link_target {
+        Some(target) => format!("{} -> {}", node.name, target.display()),
+        None => node.name.clone(),
+    };
+    match &node.error {
+        Some(error) => format!("{} [{}]", name, error),
+        None => name,
+    }
+}
+
+impl DiffRenderer for SideBySideRenderer {
+    fn render(&self, diff: &DiffNode, out: &mut dyn Write) -> Result<()> {
+        let mut rows = vec![Row {
+            left: root_label(diff.left.as_ref()),
+            marker: None,
+            right: root_label(diff.right.as_ref()),
+        }];
+        self.collect_rows(diff, "", &mut rows);
+
+        let width = rows
+            .iter()
+            .map(|row| row.left.chars().count())
+            .max()
+            .unwrap_or(0);
+        for row in rows {
+            let marker = match row.marker {
+                Some((marker, color)) => paint(marker, color, self.color),
+                None => " ".to_string(),
+            };
+            let line = format!("{:<width$}  {}  {}", row.left, marker, row.right);
+            writeln!(out, "{}", line.trim_end())?;
+        }
+        writeln!(out, "{}", diff.summary())?;
+        Ok(())
+    }
+}
+
+/// Lists every changed entry on its own line with its path, like a patch lists changed files.
+pub struct PatchRenderer;
+
+impl PatchRenderer {
+    fn render_changes(&self, diff: &DiffNode, out: &mut dyn Write) -> Result<()> {
+        for child in &diff.children {
+            let path = child.path.display();
+            let (left, right) = (child.left.as_ref(), child.right.as_ref());
+            match child.change {
+                Change::Unchanged => {}
+                Change::Added | Change::Removed => {
+                    let (marker, _) = change_style(child.change);
+                    writeln!(out, "{} {} ({})", marker, path, describe(left.or(right)))?;
+                }
+                Change::Modified => {
+                    write!(out, "~ {}", path)?;
+                    if child.contents_changed {
+                        write!(
+                            out,
+                            " ({} → {} bytes)",
+                            left.map_or(0, |node| node.size),
+                            right.map_or(0, |node| node.size)
+                        )?;
+                    }
+                    writeln!(out, "{}", attribute_changes(child))?;
+                }
+                Change::TypeChanged => writeln!(
+                    out,
+                    "! {} ({} → {})",
+                    path,
+                    left.map_or("", |node| node.kind.name()),
+                    right.map_or("", |node| node.kind.name())
+                )?,
+                Change::Moved => {
+                    let (verb, from) = moved_from(child);
+                    writeln!(out, "> {} ({} from {})", path, verb, from)?;
+                }
+            }
+            if let Some(error) = &child.error {
+                writeln!(out, "? {} [{}]", path, error)?;
+            }
+            self.render_changes(child, out)?;
+        }
+        Ok(())
+    }
+}
+
+/// Get the kind and size of an entry, like `file, 120 bytes`.
+fn describe(node: Option<&Node>) -> String {
+    match node {
+        Some(node) if node.is_dir() => node.kind.name().to_string(),
+        Some(node) => format!("{}, {} bytes", node.kind.name(), node.size),
+        None => String::new(),
+    }
+}
+
+impl DiffRenderer for PatchRenderer {
+    fn render(&self, diff: &DiffNode, out: &mut dyn Write) -> Result<()> {
+        writeln!(out, "--- {}", root_label(diff.left.as_ref()))?;
+        writeln!(out, "+++ {}", root_label(diff.right.as_ref()))?;
+        if let Some(error) = &diff.error {
+            writeln!(out, "? . [{}]", error)?;
+        }
+        self.render_changes(diff, out)?;
+        writeln!(out, "{}", diff.summary())?;
+        Ok(())
+    }
+}
+
+/// The JSON output of a comparison.
+#[derive(Serialize)]
+struct JsonDiff {
+    left: String,
+    right: String,
+    summary: DiffSummary,
+    root: JsonDiffEntry,
+}
+
+/// An entry of the JSON comparison, with the metadata of each side.
+#[derive(Serialize)]
+struct JsonDiffEntry {
+    name: String,
+    path: String,
+    change: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    left: Option<JsonDiffSide>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    right: Option<JsonDiffSide>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    moved_from: Option<String>,
+    /// Compared attributes that differ, the values are on both sides.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    changed_attributes: Vec<&'static str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    children: Vec<JsonDiffEntry>,
+}
+
+#[derive(Serialize)]
+struct JsonDiffSide {
+    entry_type: &'static str,
+    size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mode: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uid: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gid: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    modified: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    modified_epoch: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    link_target: Option<String>,
+    /// BLAKE3 hash of the contents, when comparing by hash.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hash: Option<String>,
+}
+
+impl From<&DiffNode> for JsonDiffEntry {
+    fn from(diff: &DiffNode) -> Self {
+        JsonDiffEntry {
+            name: diff.name.clone(),
+            // The roots have an empty relative path
+            path: match diff.path.as_os_str().is_empty() {
+                true => ".".to_string(),
+                false => diff.path.to_string_lossy().into_owned(),
+            },
+            change: diff.change.name(),
+            left: diff
+                .left
+                .as_ref()
+                .map(|node| JsonDiffSide::new(node, diff.left_hash.clone())),
+            right: diff
+                .right
+                .as_ref()
+                .map(|node| JsonDiffSide::new(node, diff.right_hash.clone())),
+            error: diff.error.clone(),
+            moved_from: diff
+                .moved_from
+                .as_ref()
+                .map(|from| from.to_string_lossy().into_owned()),
+            changed_attributes: diff
+                .changed_attributes
+                .iter()
+                .map(|attribute| attribute.name())
+                .collect(),
+            children: diff.children.iter().map(JsonDiffEntry::from).collect(),
+        }
+    }
+}
+
+impl JsonDiffSide {
+    fn new(node: &Node, hash: Option<String>) -> Self {
+        JsonDiffSide {
+            entry_type: node.kind.name(),
+            size: node.size,
+            mode: node.mode,
+            uid: node.uid,
+            gid: node.gid,
+            modified: node.modified.map(datetime_to_iso8601),
+            modified_epoch: node.modified.map(unix_timestamp),
+            link_target: node
+                .link_target
+                .as_ref()
+                .map(|target| target.to_string_lossy().into_owned()),
+            hash,
+        }
+    }
+}
+
+/// Serializes the comparison as pretty printed JSON, with the metadata of both sides.
+pub struct JsonDiffRenderer;
+
+impl DiffRenderer for JsonDiffRenderer {
+    fn render(&self, diff: &DiffNode, out: &mut dyn Write) -> Result<()> {
+        let json_diff = JsonDiff {
+            left: root_label(diff.left.as_ref()),
+            right: root_label(diff.right.as_ref()),
+            summary: diff.summary(),
+            root: JsonDiffEntry::from(diff),
+        };
+        serde_json::to_writer_pretty(&mut *out, &json_diff)?;
+        writeln!(out)?;
+        Ok(())
+    }
+}
+
+/// Draws a three-way comparison as one tree, with a two letter marker in front of each entry
+/// telling how our side and their side changed it from the base, like `git status --short`:
+/// `A` added, `D` deleted, `M` modified. Conflicts are flagged after the name.
+pub struct MergeTreeRenderer {
+    branch_style: BranchStyle,
+    color: bool,
+}
+
+impl MergeTreeRenderer {
+    pub fn new(options: &RenderOptions) -> Self {
+        MergeTreeRenderer {
+            branch_style: options.branch_style,
+            color: options.color,
+        }
+    }
+
+    fn render_children(&self, merge: &MergeNode, prefix: &str, out: &mut dyn Write) -> Result<()> {
+        let (branch, last_branch, continuation) = self.branch_style.prefixes();
+
+        for (i, child) in merge.children.iter().enumerate() {
+            let is_last = i == merge.children.len() - 1;
+            let new_prefix = if is_last { last_branch } else { branch };
+
+            let (ours_changed, theirs_changed) = match child.change {
+                MergeChange::Unchanged => (false, false),
+                MergeChange::ChangedInOurs
+                | MergeChange::AddedInOurs
+                | MergeChange::DeletedInOurs => (true, false),
+                MergeChange::ChangedInTheirs
+                | MergeChange::AddedInTheirs
+                | MergeChange::DeletedInTheirs => (false, true),
+                MergeChange::ChangedInBoth | MergeChange::Conflict => (true, true),
+            };
+            let marker = format!(
+                "{}{}",
+                side_code(&child.base, &child.ours, ours_changed),
+                side_code(&child.base, &child.theirs, theirs_changed)
+            );
+
+            let (name, color) = match child.change {
+                MergeChange::Unchanged => (child.name.clone(), None),
+                MergeChange::Conflict => (format!("{} (conflict)", child.name), Some(Color::Red)),
+                MergeChange::ChangedInBoth => (child.name.clone(), Some(Color::Cyan)),
+                _ if ours_changed => (child.name.clone(), Some(Color::Green)),
+                _ => (child.name.clone(), Some(Color::Yellow)),
+            };
+            let name = match &child.error {
+                Some(error) => format!("{} [{}]", name, error),
+                None => name,
+            };
+            let name = match color {
+                Some(color) => paint(&name, color, self.color),
+                None => name,
+            };
+            writeln!(out, "{} {}{}{}", marker, prefix, new_prefix, name)?;
+
+            let additional_prefix = if is_last { "    " } else { continuation };
+            self.render_children(child, &format!("{}{}", prefix, additional_prefix), out)?;
+        }
+        Ok(())
+    }
+}
+
+impl MergeRenderer for MergeTreeRenderer {
+    fn render(&self, merge: &MergeNode, out: &mut dyn Write) -> Result<()> {
+        let label = |node: &Option<Node>| root_label(node.as_ref());
+        writeln!(
+            out,
+            "Comparing {} (base) vs {} (ours) vs {} (theirs)",
+            label(&merge.base),
+            label(&merge.ours),
+            label(&merge.theirs)
+        )?;
+        writeln!(out, "   {}", merge.name)?;
+        self.render_children(merge, "", out)?;
+        writeln!(out, "{}", merge.summary())?;
+        Ok(())
+    }
+}
+
+/// Get the letter telling how one side changed an entry from the base.
+fn side_code(base: &Option<Node>, side: &Option<Node>, changed: bool) -> char {
+    match (changed, base, side) {
+        (false, _, _) => ' ',
+        (true, None, _) => 'A',
+        (true, _, None) => 'D',
+        (true, _, _) => 'M',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        compare::{compare_three_way, compare_trees, CompareOptions},
+        parse::parse_tree,
+    };
+
+    /// Two small text trees with an entry for each kind of change but moves, which need files to hash.
+    fn diff() -> DiffNode {
+        let left = "old\n├── config (0.01 KB)\n├── data.bin (1.00 KB)\n├── gone.txt\n├── same.txt\n└── src\n    └── lib.rs (0.50 KB)\n";
+        let right = "new\n├── config/\n│   └── app.toml\n├── data.bin (2.00 KB)\n├── new.txt\n├── same.txt\n└── src\n    └── lib.rs (0.50 KB)\n";
+        let parse = |text| parse_tree(text, Path::new("tree.txt")).unwrap();
+        compare_trees(&parse(left), &parse(right), &CompareOptions::default())
+    }
+
+    fn render(renderer: &dyn DiffRenderer) -> String {
+        let mut out = vec![];
+        renderer.render(&diff(), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    const SUMMARY: &str = "1 added, 1 removed, 1 modified, 1 type changed, 0 moved, 3 unchanged\n";
+
+    #[test]
+    fn renders_text_diffs() {
+        let output = render(&TextDiffRenderer::new(&RenderOptions::default()));
+        let expected = [
+            "Comparing old vs new",
+            "config (file in old, directory in new)",
+            "~ data.bin (1024 bytes in old, 2048 bytes in new)",
+            "- gone.txt",
+            "+ new.txt",
+            "= same.txt",
+            "src",
+            "  = lib.rs",
+        ];
+        assert_eq!(output, expected.join("\n") + "\n" + SUMMARY);
+    }
+
+    #[test]
+    fn renders_patch_diffs() {
+        let output = render(&PatchRenderer);
+        let expected = [
+            "--- old",
+            "+++ new",
+            "! config (file → directory)",
+            "~ data.bin (1024 → 2048 bytes)",
+            "- gone.txt (file, 0 bytes)",
+            "+ new.txt (file, 0 bytes)",
+        ];
+        assert_eq!(output, expected.join("\n") + "\n" + SUMMARY);
+    }
+
+    #[test]
+    fn renders_side_by_side_diffs() {
+        let output = render(&SideBySideRenderer::new(&RenderOptions::default()));
+        let expected = [
+            "old                new",
+            "├── config      !  ├── config",
+            "├── data.bin    ~  ├── data.bin",
+            "├── gone.txt    -",
+            "                +  ├── new.txt",
+            "├── same.txt    =  ├── same.txt",
+            "└── src            └── src",
+            "    └── lib.rs  =      └── lib.rs",
+        ];
+        assert_eq!(output, expected.join("\n") + "\n" + SUMMARY);
+    }
+
+    #[test]
+    fn renders_json_diffs() {
+        let output = render(&JsonDiffRenderer);
+        let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(
+            (json["left"].as_str(), json["right"].as_str()),
+            (Some("old"), Some("new"))
+        );
+        assert_eq!(json["summary"]["type_changed"], 1);
+
+        let children = json["root"]["children"].as_array().unwrap();
+        let changes: Vec<_> = children
+            .iter()
+            .map(|child| {
+                (
+                    child["path"].as_str().unwrap(),
+                    child["change"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                ("config", "type_changed"),
+                ("data.bin", "modified"),
+                ("gone.txt", "removed"),
+                ("new.txt", "added"),
+                ("same.txt", "unchanged"),
+                ("src", "unchanged"),
+            ]
+        );
+        let config = &children[0];
+        assert_eq!(config["left"]["entry_type"], "file");
+        assert_eq!(config["right"]["entry_type"], "directory");
+        assert!(children[2].get("right").is_none());
+        assert_eq!(children[1]["right"]["size"], 2048);
+    }
+
+    #[test]
+    fn renders_three_way_trees() {
+        let parse = |text| parse_tree(text, Path::new("tree.txt")).unwrap();
+        let base = parse(
+            "base\n├── LICENSE\n├── legacy.rs\n├── lib.rs (1.00 KB)\n└── parser.rs (1.00 KB)\n",
+        );
+        let ours = parse("ours\n├── LICENSE\n├── lib.rs (2.00 KB)\n├── parser.rs (1.00 KB)\n└── patches\n    └── fix.patch\n");
+        let theirs = parse("theirs\n├── LICENSE\n├── lib.rs (3.00 KB)\n└── parser.rs (2.00 KB)\n");
+        let merge = compare_three_way(&base, &ours, &theirs, &CompareOptions::default());
+
+        let mut out = vec![];
+        MergeTreeRenderer::new(&RenderOptions::default())
+            .render(&merge, &mut out)
+            .unwrap();
+        let expected = [
+            "Comparing base (base) vs ours (ours) vs theirs (theirs)",
+            "   base",
+            "   ├── LICENSE",
+            "DD ├── legacy.rs",
+            "MM ├── lib.rs (conflict)",
+            " M ├── parser.rs",
+            "A  └── patches",
+            "A      └── fix.patch",
+            "2 changed in ours, 1 changed in theirs, 1 changed in both, 1 conflicts, 1 unchanged",
+        ];
+        assert_eq!(String::from_utf8(out).unwrap(), expected.join("\n") + "\n");
+    }
+}
@@ -0,0 +1,54 @@
+use std::io::Write;
+
+use super::Renderer;
+use crate::{error::Result, scan::Node};
+
+/// Writes the tree as a Graphviz DOT graph, with directories as parent nodes and files as leaves.
+pub struct DotRenderer;
+
+impl DotRenderer {
+    /// Write a node and the edges to its children, `next_id` is the id of the next node to write.
+    fn render_node(
+        &self,
+        node: &Node,
+        id: usize,
+        next_id: &mut usize,
+        out: &mut dyn Write,
+    ) -> Result<()> {
+        let (label, shape) = match node.is_dir() {
+            true => (format!("{}/", node.name), "folder"),
+            false => (node.name.clone(), "note"),
+        };
+        writeln!(
+            out,
+            "  n{} [label=\"{}\", shape={}];",
+            id,
+            escape_dot(&label),
+            shape
+        )?;
+
+        for child in &node.children {
+            let child_id = *next_id;
+            *next_id += 1;
+            writeln!(out, "  n{} -> n{};", id, child_id)?;
+            self.render_node(child, child_id, next_id, out)?;
+        }
+        Ok(())
+    }
+}
+
+impl Renderer for DotRenderer {
+    fn render(&self, tree: &Node, out: &mut dyn Write) -> Result<()> {
+        writeln!(out, "digraph tree {{")?;
+        writeln!(out, "  rankdir=LR;")?;
+        writeln!(out, "  node [fontname=\"monospace\"];")?;
+        self.render_node(tree, 0, &mut 1, out)?;
+        writeln!(out, "}}")?;
+        Ok(())
+    }
+}
+
+/// Escape the characters that would end a quoted DOT string.
+fn escape_dot(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,152 @@
+use std::io::Write;
+
+use super::{
+    xml::{escape_xml, permission_string},
+    RenderOptions, Renderer,
+};
+use crate::{
+    date::{get_human_readable_date, TimeFormat},
+    error::Result,
+    icon::get_file_icon,
+    scan::{Node, TimeField},
+};
+
+const STYLE: &str = r#"
+body { font-family: system-ui, sans-serif; margin: 2rem; color: #24292f; }
+h1 { font-size: 1.4rem; }
+#filter { width: 100%; max-width: 32rem; padding: 0.4rem; margin-bottom: 1rem; font-size: 1rem; }
+ul { list-style: none; margin: 0; padding-left: 1.4rem; }
+summary { cursor: pointer; }
+.entry { display: flex; gap: 1rem; padding: 0.1rem 0; }
+.name { flex: 1; white-space: nowrap; }
+.directory > details > summary .name { color: #1a7f37; font-weight: 600; }
+.icon { font-family: "Symbols Nerd Font", "Hack Nerd Font", monospace; width: 1.2rem; display: inline-block; }
+.meta { color: #57606a; font-family: monospace; white-space: nowrap; }
+.error { color: #cf222e; }
+.hidden { display: none; }
+"#;
+
+const SCRIPT: &str = r#"
+const filter = document.getElementById("filter");
+filter.addEventListener("input", () => {
+  const query = filter.value.trim().toLowerCase();
+  // Show entries matching the query, their parents and everything inside matching directories
+  const apply = (item, parentMatches) => {
+    const matches = parentMatches || item.dataset.name.toLowerCase().includes(query);
+    let visible = matches;
+    for (const child of item.querySelectorAll(":scope > details > ul > li")) {
+      if (apply(child, matches)) visible = true;
+    }
+    item.classList.toggle("hidden", !visible);
+    const details = item.querySelector(":scope > details");
+    if (details && query !== "") details.open = visible;
+    return visible;
+  };
+  for (const item of document.querySelectorAll(".tree > ul > li")) apply(item, query === "");
+});
+"#;
+
+/// Writes a self-contained HTML report with collapsible directories and a filter box.
+pub struct HtmlRenderer {
+    title: Option<String>,
+    icons: bool,
+    time_format: TimeFormat,
+    time_field: TimeField,
+}
+
+impl HtmlRenderer {
+    pub fn new(options: &RenderOptions) -> Self {
+        HtmlRenderer {
+            title: options.title.clone(),
+            icons: options.icons,
+            time_format: options.time_format.clone(),
+            time_field: options.time_field,
+        }
+    }
+
+    fn render_node(&self, node: &Node, out: &mut dyn Write) -> Result<()> {
+        let class = if node.is_dir() { "directory" } else { "file" };
+        writeln!(
+            out,
+            "<li class=\"{}\" data-name=\"{}\">",
+            class,
+            escape_xml(&node.name)
+        )?;
+
+        if node.is_dir() {
+            writeln!(out, "<details open>")?;
+            write!(out, "<summary>")?;
+            self.render_entry(node, out)?;
+            writeln!(out, "</summary>")?;
+            if let Some(error) = &node.error {
+                writeln!(out, "<p class=\"error\">[{}]</p>", escape_xml(error))?;
+            }
+            writeln!(out, "<ul>")?;
+            for child in &node.children {
+                self.render_node(child, out)?;
+            }
+            writeln!(out, "</ul>")?;
+            writeln!(out, "</details>")?;
+        } else {
+            self.render_entry(node, out)?;
+            writeln!(out)?;
+        }
+
+        writeln!(out, "</li>")?;
+        Ok(())
+    }
+
+    /// Write the name and metadata line of an entry.
+    fn render_entry(&self, node: &Node, out: &mut dyn Write) -> Result<()> {
+        write!(out, "<span class=\"entry\"><span class=\"name\">")?;
+        if self.icons {
+            write!(
+                out,
+                "<span class=\"icon\">{}</span> ",
+                get_file_icon(&node.path)
+            )?;
+        }
+        write!(
+            out,
+            "{}</span><span class=\"meta\">{:.2} KB</span><span class=\"meta\">{}</span><span class=\"meta\">{}</span></span>",
+            escape_xml(&node.name),
+            node.size as f64 / 1024.0,
+            permission_string(node),
+            escape_xml(&get_human_readable_date(node, self.time_field, &self.time_format))
+        )?;
+        Ok(())
+    }
+}
+
+impl Renderer for HtmlRenderer {
+    fn render(&self, tree: &Node, out: &mut dyn Write) -> Result<()> {
+        let title = escape_xml(self.title.as_deref().unwrap_or(&tree.name));
+
+        writeln!(out, "<!DOCTYPE html>")?;
+        writeln!(out, "<html lang=\"en\">")?;
+        writeln!(out, "<head>")?;
+        writeln!(out, "<meta charset=\"utf-8\">")?;
+        writeln!(out, "<title>{}</title>", title)?;
+        writeln!(out, "<style>{}</style>", STYLE)?;
+        writeln!(out, "</head>")?;
+        writeln!(out, "<body>")?;
+        writeln!(out, "<h1>{}</h1>", title)?;
+        writeln!(
+            out,
+            "<input id=\"filter\" type=\"search\" placeholder=\"Filter by name\" autofocus>"
+        )?;
+        writeln!(out, "<div class=\"tree\">")?;
+        writeln!(out, "<ul>")?;
+        self.render_node(tree, out)?;
+        writeln!(out, "</ul>")?;
+        writeln!(out, "</div>")?;
+        writeln!(out, "<script>{}</script>", SCRIPT)?;
+        writeln!(out, "</body>")?;
+        writeln!(out, "</html>")?;
+        Ok(())
+    }
+
+    fn directory_sizes(&self) -> bool {
+        true
+    }
+}
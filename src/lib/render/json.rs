@@ -0,0 +1,252 @@
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{RenderOptions, Renderer};
+use crate::{
+    date::{
+        datetime_to_iso8601, from_unix_timestamp, get_human_readable_date, unix_timestamp,
+        TimeFormat, TIMESTAMP_RANGE,
+    },
+    error::{Result, TreeGenError},
+    scan::{Node, NodeKind, TimeField},
+};
+
+/// Version of the structured output schema, written on the root node.
+/// Version 1 only had the human readable strings that are now under `human`.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// A node of the JSON, YAML and TOML outputs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreeNode {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_version: Option<u32>,
+    pub name: String,
+    /// One of `file`, `directory`, `symlink`, `fifo`, `socket`, `block_device` or `char_device`.
+    pub entry_type: String,
+    /// Size in bytes, for directories the total size of the entries listed inside them.
+    pub size: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uid: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gid: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inode: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nlink: Option<u64>,
+    /// Modification time as an ISO-8601 UTC timestamp.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<String>,
+    /// Modification time in seconds since the UNIX epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified_epoch: Option<i64>,
+    /// Access time, as an ISO-8601 UTC timestamp and in seconds since the UNIX epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accessed: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accessed_epoch: Option<i64>,
+    /// Status change time, as an ISO-8601 UTC timestamp and in seconds since the UNIX epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changed: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changed_epoch: Option<i64>,
+    /// Creation time, where the filesystem records it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_epoch: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link_target: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub human: Option<HumanReadable>,
+    #[serde(default)]
+    pub children: Vec<TreeNode>,
+}
+
+/// Human readable strings of a node, only written when asked for.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HumanReadable {
+    pub size: String,
+    pub node_type: String,
+    pub permission: String,
+    pub last_modification_date: String,
+}
+
+impl TreeNode {
+    /// Read a snapshot written by the JSON output. Times outside the years 0000 to 9999 are
+    /// refused, the JSON output never writes them.
+    pub fn read_snapshot(path: &Path) -> Result<TreeNode> {
+        let content = fs::read_to_string(path).map_err(|source| TreeGenError::Path {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        let snapshot: TreeNode =
+            serde_json::from_str(&content).map_err(|err| TreeGenError::Parse {
+                path: path.to_path_buf(),
+                reason: err.to_string(),
+            })?;
+        if snapshot.schema_version != Some(SCHEMA_VERSION) {
+            return Err(TreeGenError::Parse {
+                path: path.to_path_buf(),
+                reason: format!(
+                    "unsupported schema version {:?}, expected {}",
+                    snapshot.schema_version, SCHEMA_VERSION
+                ),
+            });
+        }
+        snapshot.check_times(Path::new(&snapshot.name), path)?;
+        Ok(snapshot)
+    }
+
+    /// Check that the times of this entry and the entries below it, at `entry` in the snapshot
+    /// read from `path`, are in [`TIMESTAMP_RANGE`].
+    fn check_times(&self, entry: &Path, path: &Path) -> Result<()> {
+        let times = [
+            ("modified_epoch", self.modified_epoch),
+            ("accessed_epoch", self.accessed_epoch),
+            ("changed_epoch", self.changed_epoch),
+            ("created_epoch", self.created_epoch),
+        ];
+        for (field, time) in times {
+            if let Some(time) = time.filter(|time| !TIMESTAMP_RANGE.contains(time)) {
+                return Err(TreeGenError::Parse {
+                    path: path.to_path_buf(),
+                    reason: format!(
+                        "{} {} of '{}' is out of range",
+                        field,
+                        time,
+                        entry.display()
+                    ),
+                });
+            }
+        }
+        for child in &self.children {
+            child.check_times(&entry.join(&child.name), path)?;
+        }
+        Ok(())
+    }
+
+    /// Convert a scanned tree, with the schema version on the root node. The human readable
+    /// strings are only added with a format for their dates.
+    pub fn from_tree(tree: &Node, human_readable: Option<&TimeFormat>) -> Self {
+        TreeNode {
+            schema_version: Some(SCHEMA_VERSION),
+            ..TreeNode::from_node(tree, human_readable)
+        }
+    }
+
+    /// Convert a snapshot back into a tree of nodes rooted at `path`, with the paths of the
+    /// entries joined to it. Modification times are only known to the second.
+    pub fn to_tree(&self, path: &Path) -> Node {
+        Node {
+            name: self.name.clone(),
+            path: path.to_path_buf(),
+            kind: NodeKind::from_name(&self.entry_type).unwrap_or(NodeKind::File),
+            size: self.size,
+            mode: self.mode,
+            modified: self.modified_epoch.map(from_unix_timestamp),
+            accessed: self.accessed_epoch.map(from_unix_timestamp),
+            changed: self.changed_epoch.map(from_unix_timestamp),
+            created: self.created_epoch.map(from_unix_timestamp),
+            uid: self.uid,
+            gid: self.gid,
+            inode: self.inode,
+            nlink: self.nlink,
+            link_target: self.link_target.as_ref().map(PathBuf::from),
+            error: self.error.clone(),
+            children: self
+                .children
+                .iter()
+                .map(|child| child.to_tree(&path.join(&child.name)))
+                .collect(),
+        }
+    }
+
+    fn from_node(node: &Node, human_readable: Option<&TimeFormat>) -> Self {
+        TreeNode {
+            schema_version: None,
+            name: node.name.clone(),
+            entry_type: node.kind.name().to_string(),
+            size: node.size,
+            mode: node.mode,
+            uid: node.uid,
+            gid: node.gid,
+            inode: node.inode,
+            nlink: node.nlink,
+            modified: node.modified.map(datetime_to_iso8601),
+            modified_epoch: node.modified.map(unix_timestamp),
+            accessed: node.accessed.map(datetime_to_iso8601),
+            accessed_epoch: node.accessed.map(unix_timestamp),
+            changed: node.changed.map(datetime_to_iso8601),
+            changed_epoch: node.changed.map(unix_timestamp),
+            created: node.created.map(datetime_to_iso8601),
+            created_epoch: node.created.map(unix_timestamp),
+            link_target: node
+                .link_target
+                .as_ref()
+                .map(|target| target.to_string_lossy().into_owned()),
+            error: node.error.clone(),
+            human: human_readable.map(|time_format| HumanReadable::new(node, time_format)),
+            children: node
+                .children
+                .iter()
+                .map(|child| TreeNode::from_node(child, human_readable))
+                .collect(),
+        }
+    }
+}
+
+impl HumanReadable {
+    fn new(node: &Node, time_format: &TimeFormat) -> Self {
+        HumanReadable {
+            size: format!("{:.2} KB", node.size as f64 / 1024.0),
+            node_type: match node.kind {
+                NodeKind::Directory => "Directory",
+                NodeKind::Symlink => "Symlink",
+                _ => "File",
+            }
+            .to_string(),
+            permission: node
+                .mode
+                .map(|mode| format!("{:o}", mode))
+                .unwrap_or("permission unknown".to_string()),
+            last_modification_date: get_human_readable_date(node, TimeField::Modified, time_format),
+        }
+    }
+}
+
+/// Serializes the tree as pretty printed JSON.
+pub struct JsonRenderer {
+    /// How the human readable dates are written, when they are written.
+    human_readable: Option<TimeFormat>,
+}
+
+impl JsonRenderer {
+    pub fn new(options: &RenderOptions) -> Self {
+        JsonRenderer {
+            human_readable: options.human_readable.then(|| options.time_format.clone()),
+        }
+    }
+}
+
+impl Renderer for JsonRenderer {
+    fn render(&self, tree: &Node, out: &mut dyn Write) -> Result<()> {
+        let json_tree = TreeNode::from_tree(tree, self.human_readable.as_ref());
+        serde_json::to_writer_pretty(&mut *out, &json_tree)?;
+        writeln!(out)?;
+        Ok(())
+    }
+
+    fn directory_sizes(&self) -> bool {
+        true
+    }
+}
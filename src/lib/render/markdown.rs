@@ -0,0 +1,93 @@
+use std::io::Write;
+
+use super::{ascii::AsciiRenderer, RenderOptions, Renderer};
+use crate::{error::Result, scan::Node};
+
+/// Writes the tree ready to paste into markdown files, either as a fenced code block
+/// or as nested bullet lists linking every entry relative to the root directory.
+pub struct MarkdownRenderer {
+    title: Option<String>,
+    links: bool,
+    ascii: AsciiRenderer,
+}
+
+impl MarkdownRenderer {
+    pub fn new(options: &RenderOptions) -> Self {
+        MarkdownRenderer {
+            title: options.title.clone(),
+            links: options.markdown_links,
+            // Escape codes for colors would end up in the markdown file
+            ascii: AsciiRenderer::new(&RenderOptions {
+                color: false,
+                ..options.clone()
+            }),
+        }
+    }
+
+    /// Write the children of a directory as nested bullet lists of relative links.
+    fn render_links(
+        &self,
+        root: &Node,
+        node: &Node,
+        indent: usize,
+        out: &mut dyn Write,
+    ) -> Result<()> {
+        for child in &node.children {
+            let relative_path = child.path.strip_prefix(&root.path).unwrap_or(&child.path);
+            let mut link = encode_link(&relative_path.to_string_lossy());
+            let mut name = escape_markdown(&child.name);
+            if child.is_dir() {
+                link.push('/');
+                name.push('/');
+            }
+
+            writeln!(out, "{}- [{}]({})", "  ".repeat(indent), name, link)?;
+            self.render_links(root, child, indent + 1, out)?;
+        }
+        Ok(())
+    }
+}
+
+impl Renderer for MarkdownRenderer {
+    fn render(&self, tree: &Node, out: &mut dyn Write) -> Result<()> {
+        if let Some(title) = &self.title {
+            writeln!(out, "## {}", title)?;
+            writeln!(out)?;
+        }
+
+        if self.links {
+            return self.render_links(tree, tree, 0, out);
+        }
+
+        writeln!(out, "```text")?;
+        self.ascii.render(tree, out)?;
+        writeln!(out, "```")?;
+        Ok(())
+    }
+}
+
+/// Escape the characters that would break the text of a markdown link.
+fn escape_markdown(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | '[' | ']' | '*' | '_' | '`' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Percent-encode the characters that are not allowed in a markdown link destination.
+fn encode_link(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
@@ -0,0 +1,53 @@
+use std::io::Write;
+
+use super::Renderer;
+use crate::{error::Result, scan::Node};
+
+/// Writes the tree as a Mermaid flowchart, with directories as parent nodes and files as leaves.
+pub struct MermaidRenderer;
+
+impl MermaidRenderer {
+    /// Write the edges to the children of a node, `next_id` is the id of the next node to write.
+    fn render_children(
+        &self,
+        node: &Node,
+        id: usize,
+        next_id: &mut usize,
+        out: &mut dyn Write,
+    ) -> Result<()> {
+        for child in &node.children {
+            let child_id = *next_id;
+            *next_id += 1;
+            writeln!(out, "  n{} --> {}", id, node_definition(child, child_id))?;
+            self.render_children(child, child_id, next_id, out)?;
+        }
+        Ok(())
+    }
+}
+
+impl Renderer for MermaidRenderer {
+    fn render(&self, tree: &Node, out: &mut dyn Write) -> Result<()> {
+        writeln!(out, "graph LR")?;
+        writeln!(out, "  {}", node_definition(tree, 0))?;
+        self.render_children(tree, 0, &mut 1, out)?;
+        writeln!(out, "  classDef directory font-weight:bold")?;
+        Ok(())
+    }
+}
+
+/// Get the Mermaid node with its label, directories get the `directory` class.
+fn node_definition(node: &Node, id: usize) -> String {
+    match node.is_dir() {
+        true => format!("n{}[\"{}/\"]:::directory", id, escape_mermaid(&node.name)),
+        false => format!("n{}[\"{}\"]", id, escape_mermaid(&node.name)),
+    }
+}
+
+/// Replace the characters that would end a quoted Mermaid label with entity codes.
+fn escape_mermaid(value: &str) -> String {
+    value
+        .replace('&', "#amp;")
+        .replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+}
@@ -0,0 +1,181 @@
+use std::io::Write;
+
+use super::{
+    compare::{DiffNode, MergeNode},
+    date::TimeFormat,
+    error::Result,
+    scan::{Node, TimeField, WalkEntry},
+};
+
+pub mod ascii;
+pub mod csv;
+pub mod diff;
+pub mod dot;
+pub mod html;
+pub mod json;
+pub mod markdown;
+pub mod mermaid;
+pub mod ndjson;
+pub mod toml;
+pub mod xml;
+pub mod yaml;
+
+/// An output format for a scanned tree.
+pub trait Renderer {
+    /// Render the tree rooted at `tree` into `out`.
+    fn render(&self, tree: &Node, out: &mut dyn Write) -> Result<()>;
+
+    /// Whether the output shows the total size of directories, which the scan then sums.
+    fn directory_sizes(&self) -> bool {
+        false
+    }
+
+    /// Get this renderer as a [`StreamRenderer`] when it can write entries while the walk proceeds.
+    fn streaming(&self) -> Option<&dyn StreamRenderer> {
+        None
+    }
+}
+
+/// An output format that writes each entry on its own, so the tree never has to be held in memory.
+pub trait StreamRenderer {
+    /// Render a single entry reached by [`crate::scan::walk`] into `out`.
+    fn render_entry(&self, entry: &WalkEntry, out: &mut dyn Write) -> Result<()>;
+}
+
+/// An output format for the comparison of two trees.
+pub trait DiffRenderer {
+    /// Render the comparison rooted at `diff` into `out`.
+    fn render(&self, diff: &DiffNode, out: &mut dyn Write) -> Result<()>;
+}
+
+/// An output format for the three-way comparison of two trees with their base.
+pub trait MergeRenderer {
+    /// Render the three-way comparison rooted at `merge` into `out`.
+    fn render(&self, merge: &MergeNode, out: &mut dyn Write) -> Result<()>;
+}
+
+/// Characters used to draw the branches of a text tree.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BranchStyle {
+    Ascii,
+    #[default]
+    Unicode,
+}
+
+impl BranchStyle {
+    /// Get the (branch, last branch, continuation) prefixes for this style.
+    pub fn prefixes(&self) -> (&'static str, &'static str, &'static str) {
+        match self {
+            BranchStyle::Ascii => ("|-- ", "`-- ", "|   "),
+            BranchStyle::Unicode => ("├── ", "└── ", "│   "),
+        }
+    }
+}
+
+/// Display options shared by the renderers. Each renderer uses the ones that make sense for it.
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    pub branch_style: BranchStyle,
+    pub show_size: bool,
+    pub icons: bool,
+    pub preview_lines: Option<usize>,
+    pub color: bool,
+    /// Heading written above the tree by document formats.
+    pub title: Option<String>,
+    /// List entries as relative links in the markdown output.
+    pub markdown_links: bool,
+    /// Add human readable strings next to the raw values in structured outputs.
+    pub human_readable: bool,
+    /// Columns of the csv and tsv output, all columns when empty.
+    pub columns: Vec<csv::Column>,
+    /// How human readable dates are written.
+    pub time_format: TimeFormat,
+    /// Timestamp shown by the formats that show a single date.
+    pub time_field: TimeField,
+}
+
+type RendererFactory = fn(&RenderOptions) -> Box<dyn Renderer>;
+
+/// Every output format selectable with `--format`, by name.
+const RENDERERS: &[(&str, RendererFactory)] = &[
+    ("ascii", |options| {
+        Box::new(ascii::AsciiRenderer::new(options))
+    }),
+    ("json", |options| Box::new(json::JsonRenderer::new(options))),
+    ("ndjson", |_| Box::new(ndjson::NdjsonRenderer)),
+    ("xml", |options| Box::new(xml::XmlRenderer::new(options))),
+    ("yaml", |options| Box::new(yaml::YamlRenderer::new(options))),
+    ("toml", |options| Box::new(toml::TomlRenderer::new(options))),
+    ("markdown", |options| {
+        Box::new(markdown::MarkdownRenderer::new(options))
+    }),
+    ("html", |options| Box::new(html::HtmlRenderer::new(options))),
+    ("dot", |_| Box::new(dot::DotRenderer)),
+    ("mermaid", |_| Box::new(mermaid::MermaidRenderer)),
+    ("csv", |options| {
+        Box::new(csv::DelimitedRenderer::csv(options))
+    }),
+    ("tsv", |options| {
+        Box::new(csv::DelimitedRenderer::tsv(options))
+    }),
+];
+
+/// Names of the registered output formats.
+pub fn renderer_names() -> impl Iterator<Item = &'static str> {
+    RENDERERS.iter().map(|(name, _)| *name)
+}
+
+/// Get the renderer registered under `name`.
+pub fn get_renderer(name: &str, options: &RenderOptions) -> Option<Box<dyn Renderer>> {
+    RENDERERS
+        .iter()
+        .find(|(renderer_name, _)| *renderer_name == name)
+        .map(|(_, factory)| factory(options))
+}
+
+type DiffRendererFactory = fn(&RenderOptions) -> Box<dyn DiffRenderer>;
+
+/// Every output format selectable with `--compare-format`, by name.
+const DIFF_RENDERERS: &[(&str, DiffRendererFactory)] = &[
+    ("text", |options| {
+        Box::new(diff::TextDiffRenderer::new(options))
+    }),
+    ("json", |_| Box::new(diff::JsonDiffRenderer)),
+    ("patch", |_| Box::new(diff::PatchRenderer)),
+    ("side-by-side", |options| {
+        Box::new(diff::SideBySideRenderer::new(options))
+    }),
+];
+
+/// Names of the registered comparison formats.
+pub fn diff_renderer_names() -> impl Iterator<Item = &'static str> {
+    DIFF_RENDERERS.iter().map(|(name, _)| *name)
+}
+
+/// Get the comparison renderer registered under `name`.
+pub fn get_diff_renderer(name: &str, options: &RenderOptions) -> Option<Box<dyn DiffRenderer>> {
+    DIFF_RENDERERS
+        .iter()
+        .find(|(renderer_name, _)| *renderer_name == name)
+        .map(|(_, factory)| factory(options))
+}
+
+type MergeRendererFactory = fn(&RenderOptions) -> Box<dyn MergeRenderer>;
+
+/// Every `--compare-format` that can show a three-way comparison, by name.
+const MERGE_RENDERERS: &[(&str, MergeRendererFactory)] = &[("text", |options| {
+    Box::new(diff::MergeTreeRenderer::new(options))
+})];
+
+/// Names of the registered three-way comparison formats.
+pub fn merge_renderer_names() -> impl Iterator<Item = &'static str> {
+    MERGE_RENDERERS.iter().map(|(name, _)| *name)
+}
+
+/// Get the three-way comparison renderer registered under `name`.
+pub fn get_merge_renderer(name: &str, options: &RenderOptions) -> Option<Box<dyn MergeRenderer>> {
+    MERGE_RENDERERS
+        .iter()
+        .find(|(renderer_name, _)| *renderer_name == name)
+        .map(|(_, factory)| factory(options))
+}
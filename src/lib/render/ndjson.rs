@@ -0,0 +1,129 @@
+use std::{io::Write, path::Path};
+
+use serde::Serialize;
+
+use super::{Renderer, StreamRenderer};
+use crate::{
+    date::{datetime_to_iso8601, unix_timestamp},
+    error::Result,
+    scan::{Node, WalkEntry},
+};
+
+/// A single line of the NDJSON output.
+#[derive(Serialize)]
+struct NdjsonEntry<'a> {
+    path: String,
+    parent: Option<String>,
+    depth: usize,
+    name: &'a str,
+    entry_type: &'static str,
+    /// Directories have no size, it would need the whole tree before streaming.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+    mode: Option<u32>,
+    uid: Option<u32>,
+    gid: Option<u32>,
+    inode: Option<u64>,
+    nlink: Option<u64>,
+    modified: Option<String>,
+    modified_epoch: Option<i64>,
+    accessed: Option<String>,
+    accessed_epoch: Option<i64>,
+    changed: Option<String>,
+    changed_epoch: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created_epoch: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    link_target: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'a str>,
+}
+
+/// Writes one JSON object per line for every entry, as the walk reaches it.
+pub struct NdjsonRenderer;
+
+impl NdjsonRenderer {
+    /// Write an entry of an already scanned tree, then its children.
+    fn render_node(
+        &self,
+        root: &Node,
+        node: &Node,
+        depth: usize,
+        out: &mut dyn Write,
+    ) -> Result<()> {
+        let entry = WalkEntry {
+            node,
+            root: &root.path,
+            depth,
+        };
+        self.render_entry(&entry, out)?;
+
+        for child in &node.children {
+            self.render_node(root, child, depth + 1, out)?;
+        }
+        Ok(())
+    }
+}
+
+impl Renderer for NdjsonRenderer {
+    fn render(&self, tree: &Node, out: &mut dyn Write) -> Result<()> {
+        self.render_node(tree, tree, 0, out)
+    }
+
+    fn streaming(&self) -> Option<&dyn StreamRenderer> {
+        Some(self)
+    }
+}
+
+impl StreamRenderer for NdjsonRenderer {
+    fn render_entry(&self, entry: &WalkEntry, out: &mut dyn Write) -> Result<()> {
+        let node = entry.node;
+        let line = NdjsonEntry {
+            path: relative_path(entry.root, &node.path),
+            parent: match entry.depth {
+                0 => None,
+                _ => node
+                    .path
+                    .parent()
+                    .map(|parent| relative_path(entry.root, parent)),
+            },
+            depth: entry.depth,
+            name: &node.name,
+            entry_type: node.kind.name(),
+            size: (!node.is_dir()).then_some(node.size),
+            mode: node.mode,
+            uid: node.uid,
+            gid: node.gid,
+            inode: node.inode,
+            nlink: node.nlink,
+            modified: node.modified.map(datetime_to_iso8601),
+            modified_epoch: node.modified.map(unix_timestamp),
+            accessed: node.accessed.map(datetime_to_iso8601),
+            accessed_epoch: node.accessed.map(unix_timestamp),
+            changed: node.changed.map(datetime_to_iso8601),
+            changed_epoch: node.changed.map(unix_timestamp),
+            created: node.created.map(datetime_to_iso8601),
+            created_epoch: node.created.map(unix_timestamp),
+            link_target: node
+                .link_target
+                .as_ref()
+                .map(|target| target.to_string_lossy().into_owned()),
+            error: node.error.as_deref(),
+        };
+
+        serde_json::to_writer(&mut *out, &line)?;
+        writeln!(out)?;
+        Ok(())
+    }
+}
+
+/// Get the path of an entry relative to the root, `.` for the root itself.
+fn relative_path(root: &Path, path: &Path) -> String {
+    match path.strip_prefix(root) {
+        Ok(relative) if relative.as_os_str().is_empty() => ".".to_string(),
+        Ok(relative) => relative.to_string_lossy().into_owned(),
+        Err(_) => path.to_string_lossy().into_owned(),
+    }
+}
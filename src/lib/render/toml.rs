@@ -0,0 +1,32 @@
+use std::io::Write;
+
+use super::{json::TreeNode, RenderOptions, Renderer};
+use crate::{date::TimeFormat, error::Result, scan::Node};
+
+/// Serializes the tree as TOML, with the same keys and key order as the JSON output.
+/// Children are written as nested arrays of tables.
+pub struct TomlRenderer {
+    /// How the human readable dates are written, when they are written.
+    human_readable: Option<TimeFormat>,
+}
+
+impl TomlRenderer {
+    pub fn new(options: &RenderOptions) -> Self {
+        TomlRenderer {
+            human_readable: options.human_readable.then(|| options.time_format.clone()),
+        }
+    }
+}
+
+impl Renderer for TomlRenderer {
+    fn render(&self, tree: &Node, out: &mut dyn Write) -> Result<()> {
+        let toml_tree =
+            toml::to_string_pretty(&TreeNode::from_tree(tree, self.human_readable.as_ref()))?;
+        out.write_all(toml_tree.as_bytes())?;
+        Ok(())
+    }
+
+    fn directory_sizes(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,151 @@
+use std::io::Write;
+
+use super::{RenderOptions, Renderer};
+use crate::{
+    date::{get_human_readable_date, TimeFormat},
+    error::Result,
+    scan::{Node, NodeKind, TimeField},
+};
+
+/// Writes an XML document with the same layout and attributes as GNU `tree -X`.
+pub struct XmlRenderer {
+    time_format: TimeFormat,
+    time_field: TimeField,
+}
+
+impl XmlRenderer {
+    pub fn new(options: &RenderOptions) -> Self {
+        XmlRenderer {
+            time_format: options.time_format.clone(),
+            time_field: options.time_field,
+        }
+    }
+
+    fn render_node(&self, node: &Node, indent: usize, out: &mut dyn Write) -> Result<()> {
+        let tag = match node.kind {
+            NodeKind::Directory => "directory",
+            NodeKind::Symlink => "link",
+            _ => "file",
+        };
+        let padding = "  ".repeat(indent);
+
+        write!(
+            out,
+            "{}<{} name=\"{}\"",
+            padding,
+            tag,
+            escape_xml(&node.name)
+        )?;
+        if let Some(mode) = node.mode {
+            write!(
+                out,
+                " mode=\"{:04o}\" prot=\"{}\"",
+                mode & 0o7777,
+                permission_string(node)
+            )?;
+        }
+        // `tree -X` writes the symlink target as an attribute
+        if let Some(target) = &node.link_target {
+            write!(out, " target=\"{}\"", escape_xml(&target.to_string_lossy()))?;
+        }
+        write!(
+            out,
+            " size=\"{}\" time=\"{}\">",
+            node.size,
+            escape_xml(&get_human_readable_date(
+                node,
+                self.time_field,
+                &self.time_format
+            ))
+        )?;
+
+        if !node.is_dir() {
+            writeln!(out, "</{}>", tag)?;
+            return Ok(());
+        }
+
+        writeln!(out)?;
+        // Report unreadable directories inline, like `tree -X` does
+        if let Some(error) = &node.error {
+            writeln!(out, "{}  <error>{}</error>", padding, escape_xml(error))?;
+        }
+        for child in &node.children {
+            self.render_node(child, indent + 1, out)?;
+        }
+        writeln!(out, "{}</{}>", padding, tag)?;
+        Ok(())
+    }
+}
+
+impl Renderer for XmlRenderer {
+    fn render(&self, tree: &Node, out: &mut dyn Write) -> Result<()> {
+        let (directories, files) = count_entries(tree);
+
+        writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(out, "<tree>")?;
+        self.render_node(tree, 1, out)?;
+        writeln!(out, "  <report>")?;
+        writeln!(out, "    <directories>{}</directories>", directories)?;
+        writeln!(out, "    <files>{}</files>", files)?;
+        writeln!(out, "  </report>")?;
+        writeln!(out, "</tree>")?;
+        Ok(())
+    }
+
+    fn directory_sizes(&self) -> bool {
+        true
+    }
+}
+
+/// Count the (directories, files) below a node, like the report at the end of `tree`.
+fn count_entries(node: &Node) -> (usize, usize) {
+    node.children
+        .iter()
+        .fold((0, 0), |(directories, files), child| {
+            let (child_directories, child_files) = count_entries(child);
+            if child.is_dir() {
+                (directories + child_directories + 1, files + child_files)
+            } else {
+                (directories, files + 1)
+            }
+        })
+}
+
+/// Get the `ls -l` style permission string of a node, e.g. `drwxr-xr-x`.
+pub fn permission_string(node: &Node) -> String {
+    let mode = node.mode.unwrap_or(0);
+    let file_type = match node.kind {
+        NodeKind::File => '-',
+        NodeKind::Directory => 'd',
+        NodeKind::Symlink => 'l',
+        NodeKind::Fifo => 'p',
+        NodeKind::Socket => 's',
+        NodeKind::BlockDevice => 'b',
+        NodeKind::CharDevice => 'c',
+    };
+
+    let mut permission = String::from(file_type);
+    for shift in [6, 3, 0] {
+        let bits = (mode >> shift) & 0o7;
+        permission.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        permission.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        permission.push(if bits & 0o1 != 0 { 'x' } else { '-' });
+    }
+    permission
+}
+
+/// Escape the characters that are not allowed in XML text and attribute values.
+pub fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,32 @@
+use std::io::Write;
+
+use super::{json::TreeNode, RenderOptions, Renderer};
+use crate::{date::TimeFormat, error::Result, scan::Node};
+
+/// Serializes the tree as YAML, with the same keys and key order as the JSON output.
+pub struct YamlRenderer {
+    /// How the human readable dates are written, when they are written.
+    human_readable: Option<TimeFormat>,
+}
+
+impl YamlRenderer {
+    pub fn new(options: &RenderOptions) -> Self {
+        YamlRenderer {
+            human_readable: options.human_readable.then(|| options.time_format.clone()),
+        }
+    }
+}
+
+impl Renderer for YamlRenderer {
+    fn render(&self, tree: &Node, out: &mut dyn Write) -> Result<()> {
+        serde_norway::to_writer(
+            &mut *out,
+            &TreeNode::from_tree(tree, self.human_readable.as_ref()),
+        )?;
+        Ok(())
+    }
+
+    fn directory_sizes(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,330 @@
+use std::{
+    collections::HashSet,
+    fs::{self, File, OpenOptions, Permissions},
+    io,
+    os::unix::fs::{symlink, MetadataExt, PermissionsExt},
+    path::{Component, Path, PathBuf},
+};
+
+use super::{
+    date::from_unix_timestamp,
+    error::{Result, TreeGenError},
+    render::json::TreeNode,
+    scan::NodeKind,
+};
+
+/// How the entries of a snapshot are recreated.
+#[derive(Debug, Clone, Default)]
+pub struct RestoreOptions {
+    /// Fill files with zeros up to their recorded size instead of leaving them empty.
+    pub fill: bool,
+}
+
+/// Number of entries created by [`restore`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RestoreSummary {
+    pub directories: usize,
+    pub files: usize,
+    pub symlinks: usize,
+    /// Entries that cannot be recreated, like fifos, sockets and devices.
+    pub skipped: usize,
+}
+
+/// Recreate the entries of a snapshot inside `dest`, with their recorded permissions (without
+/// the setuid, setgid and sticky bits) and modification times. `dest` stands for the snapshot root and is created when missing.
+/// Snapshots may come from anywhere, so nothing is written outside `dest` and no symlink is
+/// followed, not even one restored from the snapshot itself.
+pub fn restore(
+    snapshot: &TreeNode,
+    dest: &Path,
+    options: &RestoreOptions,
+) -> Result<RestoreSummary> {
+    // Check the whole snapshot first so a bad one writes nothing
+    check_entries(snapshot, dest)?;
+    fs::create_dir_all(dest).map_err(|err| restore_error(dest, err))?;
+
+    let mut summary = RestoreSummary::default();
+    for child in &snapshot.children {
+        restore_node(child, dest, options, &mut summary)?;
+    }
+    Ok(summary)
+}
+
+fn restore_node(
+    node: &TreeNode,
+    parent: &Path,
+    options: &RestoreOptions,
+    summary: &mut RestoreSummary,
+) -> Result<()> {
+    let path = entry_path(parent, &node.name)?;
+
+    let entry = match NodeKind::from_name(&node.entry_type) {
+        Some(NodeKind::Directory) => {
+            create_dir(&path).map_err(|err| restore_error(&path, err))?;
+            for child in &node.children {
+                restore_node(child, &path, options, summary)?;
+            }
+            summary.directories += 1;
+            open_restored_dir(&path).map_err(|err| restore_error(&path, err))?
+        }
+        Some(NodeKind::File) => {
+            // Creating a new file fails on anything already there, symlinks included
+            let file = OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+                .map_err(|err| restore_error(&path, err))?;
+            if options.fill {
+                file.set_len(node.size)
+                    .map_err(|err| restore_error(&path, err))?;
+            }
+            summary.files += 1;
+            file
+        }
+        Some(NodeKind::Symlink) => {
+            let target = node.link_target.as_deref().unwrap_or_default();
+            symlink(target, &path).map_err(|err| restore_error(&path, err))?;
+            summary.symlinks += 1;
+            // Symlinks have no permissions and their times cannot be set without following them
+            return Ok(());
+        }
+        _ => {
+            summary.skipped += 1;
+            return Ok(());
+        }
+    };
+
+    // Apply the metadata last, a read-only directory could not be filled otherwise. It is set
+    // through the open entry, never through its path that could lead somewhere else.
+    if let Some(epoch) = node.modified_epoch {
+        entry
+            .set_modified(from_unix_timestamp(epoch))
+            .map_err(|err| restore_error(&path, err))?;
+    }
+    // The setuid, setgid and sticky bits of a snapshot are not trusted
+    if let Some(mode) = node.mode {
+        entry
+            .set_permissions(Permissions::from_mode(mode & 0o777))
+            .map_err(|err| restore_error(&path, err))?;
+    }
+    Ok(())
+}
+
+/// Check that every name of a snapshot is a plain file name, listed once in its directory.
+/// A second entry with the same name would be restored over or through the first one, like a
+/// directory restored through a symlink listed before it.
+fn check_entries(node: &TreeNode, parent: &Path) -> Result<()> {
+    let mut names = HashSet::new();
+    for child in &node.children {
+        let path = entry_path(parent, &child.name)?;
+        if !names.insert(child.name.as_str()) {
+            return Err(TreeGenError::Restore {
+                path,
+                reason: "entry is listed more than once".to_string(),
+            });
+        }
+        check_entries(child, &path)?;
+    }
+    Ok(())
+}
+
+/// Create a directory, or reuse one that is already there. Anything else in its place, like
+/// a symlink to a directory, is refused.
+pub(crate) fn create_dir(path: &Path) -> io::Result<()> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => Ok(()),
+        Ok(_) => Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "already exists and is not a directory",
+        )),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => fs::create_dir(path),
+        Err(err) => Err(err),
+    }
+}
+
+/// Open a restored directory to set its metadata, refusing it when it was replaced by a
+/// symlink since it was created.
+fn open_restored_dir(path: &Path) -> io::Result<File> {
+    let created = fs::symlink_metadata(path)?;
+    let dir = File::open(path)?;
+    let opened = dir.metadata()?;
+    match created.is_dir() && (created.dev(), created.ino()) == (opened.dev(), opened.ino()) {
+        true => Ok(dir),
+        false => Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "was replaced while restoring",
+        )),
+    }
+}
+
+/// Join the name of an entry to its parent. A name must stay inside its parent, a crafted
+/// snapshot or tree could otherwise write anywhere.
+pub(crate) fn entry_path(parent: &Path, name: &str) -> Result<PathBuf> {
+    let mut components = Path::new(name).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) => Ok(parent.join(name)),
+        _ => Err(TreeGenError::Restore {
+            path: parent.join(name),
+            reason: "entry name is not a plain file name".to_string(),
+        }),
+    }
+}
+
+pub(crate) fn restore_error(path: &Path, err: io::Error) -> TreeGenError {
+    TreeGenError::Restore {
+        path: path.to_path_buf(),
+        reason: err.to_string(),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    /// A fresh directory for one test, holding the restore destination and a victim directory
+    /// that must stay untouched. It is removed when the test ends.
+    pub(crate) struct Scratch {
+        root: PathBuf,
+        pub dest: PathBuf,
+        pub victim: PathBuf,
+    }
+
+    impl Scratch {
+        pub fn new(name: &str) -> Scratch {
+            let root = env::temp_dir().join(format!("tree_gen_{}_{}", name, process::id()));
+            let _ = fs::remove_dir_all(&root);
+            let victim = root.join("victim");
+            fs::create_dir_all(&victim).unwrap();
+            fs::set_permissions(&victim, Permissions::from_mode(0o755)).unwrap();
+            Scratch {
+                dest: root.join("dest"),
+                victim,
+                root,
+            }
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.root);
+        }
+    }
+
+    pub(crate) fn assert_untouched(victim: &Path) {
+        assert!(!victim.join("planted").exists());
+        let mode = fs::metadata(victim).unwrap().permissions().mode();
+        assert_eq!(mode & 0o7777, 0o755);
+    }
+
+    fn snapshot(json: &str) -> TreeNode {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn refuses_a_directory_listed_after_a_symlink_of_the_same_name() {
+        let scratch = Scratch::new("restore_duplicate");
+        let (dest, victim) = (&scratch.dest, &scratch.victim);
+        let snapshot = snapshot(&format!(
+            r#"{{"name":"root","entry_type":"directory","size":0,"children":[
+                {{"name":"a","entry_type":"symlink","size":0,"link_target":"{}"}},
+                {{"name":"a","entry_type":"directory","size":0,"mode":511,"children":[
+                    {{"name":"planted","entry_type":"file","size":0,"mode":511}}
+                ]}}
+            ]}}"#,
+            victim.display()
+        ));
+
+        let result = restore(&snapshot, dest, &RestoreOptions::default());
+        assert!(matches!(result, Err(TreeGenError::Restore { .. })));
+        assert_untouched(victim);
+        // Nothing is written for a snapshot that fails the checks
+        assert!(!dest.exists());
+    }
+
+    #[test]
+    fn refuses_to_restore_a_directory_through_an_existing_symlink() {
+        let scratch = Scratch::new("restore_existing_symlink");
+        let (dest, victim) = (&scratch.dest, &scratch.victim);
+        fs::create_dir_all(dest).unwrap();
+        symlink(victim, dest.join("a")).unwrap();
+        let snapshot = snapshot(
+            r#"{"name":"root","entry_type":"directory","size":0,"children":[
+                {"name":"a","entry_type":"directory","size":0,"mode":511,"children":[
+                    {"name":"planted","entry_type":"file","size":0}
+                ]}
+            ]}"#,
+        );
+
+        let result = restore(&snapshot, dest, &RestoreOptions::default());
+        assert!(matches!(result, Err(TreeGenError::Restore { .. })));
+        assert_untouched(victim);
+    }
+
+    #[test]
+    fn refuses_names_leaving_their_directory() {
+        let scratch = Scratch::new("restore_names");
+        let dest = &scratch.dest;
+        for name in ["..", "../escape", "a/b", "/tmp", ""] {
+            let snapshot = snapshot(&format!(
+                r#"{{"name":"root","entry_type":"directory","size":0,"children":[
+                    {{"name":"{}","entry_type":"file","size":0}}
+                ]}}"#,
+                name
+            ));
+            let result = restore(&snapshot, dest, &RestoreOptions::default());
+            assert!(result.is_err(), "{:?} was restored", name);
+        }
+    }
+
+    #[test]
+    fn restores_modes_and_times() {
+        let scratch = Scratch::new("restore_metadata");
+        let dest = &scratch.dest;
+        let snapshot = snapshot(
+            r#"{"name":"root","entry_type":"directory","size":0,"children":[
+                {"name":"dir","entry_type":"directory","size":0,"mode":448,"modified_epoch":1000000000,"children":[
+                    {"name":"file","entry_type":"file","size":3,"mode":420,"modified_epoch":1234567890}
+                ]},
+                {"name":"link","entry_type":"symlink","size":0,"link_target":"dir/file"}
+            ]}"#,
+        );
+
+        let summary = restore(&snapshot, dest, &RestoreOptions { fill: true }).unwrap();
+        assert_eq!(
+            (summary.directories, summary.files, summary.symlinks),
+            (1, 1, 1)
+        );
+
+        let dir = fs::metadata(dest.join("dir")).unwrap();
+        assert_eq!(dir.permissions().mode() & 0o7777, 0o700);
+        assert_eq!(dir.mtime(), 1000000000);
+        let file = fs::metadata(dest.join("dir/file")).unwrap();
+        assert_eq!(file.permissions().mode() & 0o7777, 0o644);
+        assert_eq!((file.len(), file.mtime()), (3, 1234567890));
+        assert_eq!(
+            fs::read_link(dest.join("link")).unwrap(),
+            Path::new("dir/file")
+        );
+    }
+
+    #[test]
+    fn drops_setuid_setgid_and_sticky_bits() {
+        let scratch = Scratch::new("restore_special_bits");
+        let dest = &scratch.dest;
+        // 0o4755 and 0o3777
+        let snapshot = snapshot(
+            r#"{"name":"root","entry_type":"directory","size":0,"children":[
+                {"name":"dir","entry_type":"directory","size":0,"mode":2047,"children":[]},
+                {"name":"file","entry_type":"file","size":0,"mode":2541}
+            ]}"#,
+        );
+
+        restore(&snapshot, dest, &RestoreOptions::default()).unwrap();
+        let dir = fs::metadata(dest.join("dir")).unwrap();
+        assert_eq!(dir.permissions().mode() & 0o7777, 0o777);
+        let file = fs::metadata(dest.join("file")).unwrap();
+        assert_eq!(file.permissions().mode() & 0o7777, 0o755);
+    }
+}
@@ -0,0 +1,176 @@
+use std::{
+    fs::{self, OpenOptions},
+    io,
+    os::unix::fs::symlink,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    error::{Result, TreeGenError},
+    restore::{create_dir, entry_path, restore_error},
+    scan::{Node, NodeKind},
+};
+
+/// An entry that [`create`] makes on disk.
+#[derive(Debug, Clone)]
+pub struct PlannedEntry {
+    pub path: PathBuf,
+    /// A directory, a file or a symlink, other kinds are not planned.
+    pub kind: NodeKind,
+    pub link_target: Option<PathBuf>,
+    /// Whether an entry of the same kind is already there, existing entries are left untouched.
+    pub exists: bool,
+}
+
+/// List the directories, empty files and symlinks needed to lay out `tree` inside `dest`,
+/// parents first. `dest` stands for the root of the tree.
+///
+/// Nothing is planned through a symlink, so an entry drawn inside a symlink, or in place of
+/// something that is not a directory on disk, is refused before anything is created.
+pub fn plan(tree: &Node, dest: &Path) -> Result<Vec<PlannedEntry>> {
+    let mut entries = vec![];
+    plan_children(tree, dest, &mut entries)?;
+    Ok(entries)
+}
+
+fn plan_children(node: &Node, parent: &Path, entries: &mut Vec<PlannedEntry>) -> Result<()> {
+    for child in &node.children {
+        let path = entry_path(parent, &child.name)?;
+        let kind = match child.kind {
+            NodeKind::Directory | NodeKind::Symlink => child.kind,
+            _ => NodeKind::File,
+        };
+        if kind == NodeKind::Symlink && !child.children.is_empty() {
+            return Err(TreeGenError::Restore {
+                path,
+                reason: "symlink has entries drawn inside it".to_string(),
+            });
+        }
+        entries.push(PlannedEntry {
+            exists: exists_as(&path, kind).map_err(|err| restore_error(&path, err))?,
+            path: path.clone(),
+            kind,
+            link_target: child.link_target.clone(),
+        });
+        plan_children(child, &path, entries)?;
+    }
+    Ok(())
+}
+
+/// Check whether an entry of `kind` is already at `path`, without following a symlink there.
+/// An entry of another kind is an error.
+fn exists_as(path: &Path, kind: NodeKind) -> io::Result<bool> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if NodeKind::from_file_type(metadata.file_type()) == kind => Ok(true),
+        Ok(_) => Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("already exists and is not a {}", kind.name()),
+        )),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+/// Create the planned entries that do not exist yet.
+pub fn create(dest: &Path, entries: &[PlannedEntry]) -> Result<()> {
+    fs::create_dir_all(dest).map_err(|err| restore_error(dest, err))?;
+
+    for entry in entries.iter().filter(|entry| !entry.exists) {
+        match entry.kind {
+            // Refuses a symlink put in place of the directory since it was planned
+            NodeKind::Directory => create_dir(&entry.path),
+            NodeKind::Symlink => symlink(
+                entry.link_target.as_deref().unwrap_or(Path::new("")),
+                &entry.path,
+            ),
+            _ => OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&entry.path)
+                .map(|_| ()),
+        }
+        .map_err(|err| restore_error(&entry.path, err))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        parse::parse_tree,
+        restore::tests::{assert_untouched, Scratch},
+    };
+
+    fn scaffold(text: &str, dest: &Path) -> Result<Vec<PlannedEntry>> {
+        let tree = parse_tree(text, Path::new("layout.txt")).unwrap();
+        let entries = plan(&tree, dest)?;
+        create(dest, &entries)?;
+        Ok(entries)
+    }
+
+    #[test]
+    fn creates_directories_files_and_symlinks() {
+        let scratch = Scratch::new("scaffold_layout");
+        let dest = &scratch.dest;
+        scaffold(
+            "project\n├── src/\n│   └── main.rs\n├── docs/\n└── latest -> src/main.rs\n",
+            dest,
+        )
+        .unwrap();
+
+        assert!(fs::symlink_metadata(dest.join("src")).unwrap().is_dir());
+        assert!(fs::symlink_metadata(dest.join("docs")).unwrap().is_dir());
+        assert!(fs::symlink_metadata(dest.join("src/main.rs"))
+            .unwrap()
+            .is_file());
+        assert_eq!(
+            fs::read_link(dest.join("latest")).unwrap(),
+            Path::new("src/main.rs")
+        );
+
+        // A second run finds everything in place
+        let entries = scaffold("project\n├── src/\n└── latest -> src/main.rs\n", dest).unwrap();
+        assert!(entries.iter().all(|entry| entry.exists));
+    }
+
+    #[test]
+    fn refuses_entries_drawn_inside_a_symlink() {
+        let scratch = Scratch::new("scaffold_symlink_children");
+        let (dest, victim) = (&scratch.dest, &scratch.victim);
+        let text = format!(
+            "project\n├── first\n└── escape -> {}\n    └── planted\n",
+            victim.display()
+        );
+
+        let result = scaffold(&text, dest);
+        assert!(matches!(result, Err(TreeGenError::Restore { .. })));
+        assert_untouched(victim);
+        // Nothing is created for a layout that fails the plan
+        assert!(!dest.exists());
+    }
+
+    #[test]
+    fn refuses_to_create_through_an_existing_symlink() {
+        let scratch = Scratch::new("scaffold_existing_symlink");
+        let (dest, victim) = (&scratch.dest, &scratch.victim);
+        fs::create_dir_all(dest).unwrap();
+        symlink(victim, dest.join("a")).unwrap();
+
+        let result = scaffold("project\n└── a/\n    └── planted\n", dest);
+        assert!(matches!(result, Err(TreeGenError::Restore { .. })));
+        assert_untouched(victim);
+    }
+
+    #[test]
+    fn refuses_an_existing_entry_of_another_kind() {
+        let scratch = Scratch::new("scaffold_existing_file");
+        let dest = &scratch.dest;
+        fs::create_dir_all(dest).unwrap();
+        fs::write(dest.join("src"), "").unwrap();
+
+        let result = scaffold("project\n└── src/\n    └── main.rs\n", dest);
+        assert!(matches!(result, Err(TreeGenError::Restore { .. })));
+        assert!(!dest.join("src/main.rs").exists());
+    }
+}
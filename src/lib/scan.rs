@@ -0,0 +1,512 @@
+use std::{
+    cmp::Reverse,
+    convert::Infallible,
+    fs::{self, DirEntry, FileType, Metadata},
+    io, mem,
+    os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt},
+    path::{Path, PathBuf},
+    result,
+    time::{Duration, SystemTime},
+};
+
+use regex::Regex;
+
+use super::{
+    date::from_unix_timestamp,
+    error::{Result, TreeGenError},
+    filter::{matches_entry_filters, DateFilter},
+};
+
+/// Kind of a scanned filesystem entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    File,
+    Directory,
+    Symlink,
+    Fifo,
+    Socket,
+    BlockDevice,
+    CharDevice,
+}
+
+impl NodeKind {
+    /// Get the kind of an entry from its file type, which must not follow symlinks.
+    pub fn from_file_type(file_type: FileType) -> NodeKind {
+        if file_type.is_dir() {
+            NodeKind::Directory
+        } else if file_type.is_symlink() {
+            NodeKind::Symlink
+        } else if file_type.is_fifo() {
+            NodeKind::Fifo
+        } else if file_type.is_socket() {
+            NodeKind::Socket
+        } else if file_type.is_block_device() {
+            NodeKind::BlockDevice
+        } else if file_type.is_char_device() {
+            NodeKind::CharDevice
+        } else {
+            NodeKind::File
+        }
+    }
+
+    /// Every kind, in the order of their names.
+    pub const ALL: [NodeKind; 7] = [
+        NodeKind::File,
+        NodeKind::Directory,
+        NodeKind::Symlink,
+        NodeKind::Fifo,
+        NodeKind::Socket,
+        NodeKind::BlockDevice,
+        NodeKind::CharDevice,
+    ];
+
+    /// Get the kind from its name in the structured outputs.
+    pub fn from_name(name: &str) -> Option<NodeKind> {
+        NodeKind::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    /// Name of the kind used by the structured outputs.
+    pub fn name(&self) -> &'static str {
+        match self {
+            NodeKind::File => "file",
+            NodeKind::Directory => "directory",
+            NodeKind::Symlink => "symlink",
+            NodeKind::Fifo => "fifo",
+            NodeKind::Socket => "socket",
+            NodeKind::BlockDevice => "block_device",
+            NodeKind::CharDevice => "char_device",
+        }
+    }
+}
+
+/// One of the timestamps of an entry.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TimeField {
+    #[default]
+    Modified,
+    Accessed,
+    /// Last change of the contents or the metadata, `st_ctime`.
+    Changed,
+    /// Creation time, only on filesystems that record it.
+    Created,
+}
+
+impl TimeField {
+    /// Every timestamp selectable with `--time`.
+    pub const ALL: [TimeField; 4] = [
+        TimeField::Modified,
+        TimeField::Accessed,
+        TimeField::Changed,
+        TimeField::Created,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimeField::Modified => "mtime",
+            TimeField::Accessed => "atime",
+            TimeField::Changed => "ctime",
+            TimeField::Created => "btime",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<TimeField> {
+        TimeField::ALL
+            .into_iter()
+            .find(|field| field.name() == name)
+    }
+
+    /// Get this timestamp of a node.
+    pub fn of(&self, node: &Node) -> Option<SystemTime> {
+        match self {
+            TimeField::Modified => node.modified,
+            TimeField::Accessed => node.accessed,
+            TimeField::Changed => node.changed,
+            TimeField::Created => node.created,
+        }
+    }
+
+    /// Read this timestamp from the metadata of an entry.
+    fn of_metadata(&self, metadata: &Metadata) -> Option<SystemTime> {
+        match self {
+            TimeField::Modified => metadata.modified().ok(),
+            TimeField::Accessed => metadata.accessed().ok(),
+            TimeField::Changed => Some(
+                from_unix_timestamp(metadata.ctime())
+                    + Duration::from_nanos(metadata.ctime_nsec() as u64),
+            ),
+            // Read with statx on Linux, fails where the filesystem does not record it
+            TimeField::Created => metadata.created().ok(),
+        }
+    }
+}
+
+/// Order of the entries of a directory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    #[default]
+    Name,
+    /// Newest first by the selected timestamp, like `ls -t`. Entries without it come last.
+    Time,
+}
+
+impl SortOrder {
+    /// Every order selectable with `--sort`.
+    pub const ALL: [SortOrder; 2] = [SortOrder::Name, SortOrder::Time];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SortOrder::Name => "name",
+            SortOrder::Time => "time",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<SortOrder> {
+        SortOrder::ALL
+            .into_iter()
+            .find(|order| order.name() == name)
+    }
+}
+
+/// A single entry of the scanned tree, with the metadata every output mode needs.
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub name: String,
+    pub path: PathBuf,
+    pub kind: NodeKind,
+    /// Size in bytes. For directories this is the total size of the entries listed inside it
+    /// when the scan sums directory sizes, and 0 otherwise.
+    pub size: u64,
+    /// Full `st_mode`, with the file type and permission bits.
+    pub mode: Option<u32>,
+    pub modified: Option<SystemTime>,
+    pub accessed: Option<SystemTime>,
+    /// Last change of the contents or the metadata.
+    pub changed: Option<SystemTime>,
+    /// Creation time, missing where the filesystem does not record it.
+    pub created: Option<SystemTime>,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    pub inode: Option<u64>,
+    pub nlink: Option<u64>,
+    /// Where a symlink points to. Symlinks are listed, not followed.
+    pub link_target: Option<PathBuf>,
+    /// Why the directory could not be read, its children are missing when set.
+    pub error: Option<String>,
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    pub fn is_dir(&self) -> bool {
+        self.kind == NodeKind::Directory
+    }
+
+    /// Whether this is a regular file.
+    pub fn is_file(&self) -> bool {
+        self.kind == NodeKind::File
+    }
+}
+
+/// Filters and limits applied while scanning a directory.
+#[derive(Debug, Clone, Default)]
+pub struct ScanOptions {
+    pub max_depth: Option<usize>,
+    pub ignore_hidden: bool,
+    pub file_extension: Option<String>,
+    pub size_min: Option<u64>,
+    pub size_max: Option<u64>,
+    pub date_filter: Option<DateFilter>,
+    pub include: Option<Regex>,
+    pub exclude: Option<Regex>,
+    /// Timestamp the date filter and the time order use.
+    pub time_field: TimeField,
+    pub sort: SortOrder,
+    /// Sum the sizes of the entries listed in each directory into its size.
+    pub directory_sizes: bool,
+}
+
+/// Walk `path` once and build the filtered in-memory tree that every output mode renders.
+pub fn scan(path: &Path, options: &ScanOptions) -> Result<Node> {
+    let metadata = fs::metadata(path).map_err(|source| TreeGenError::Path {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    let root = build_node(path, root_name(path), Some(metadata));
+    let mut tree = TreeCollector::new(options.directory_sizes);
+    let mut walker = Walker::new(options, &read_disk_entries, |node, depth| {
+        tree.push(node, depth);
+        Ok::<_, Infallible>(())
+    });
+    let Ok(()) = walker.walk_node(root, 0);
+    Ok(tree.finish())
+}
+
+/// Apply the filters and depth of a scan to a tree that was not scanned, like a parsed text
+/// tree or a snapshot, with the same rules as [`scan`].
+pub fn filter_tree(tree: Node, options: &ScanOptions) -> Node {
+    // The directories of a tree that was not scanned keep the sizes it records
+    let mut filtered = TreeCollector::new(false);
+    let mut walker = Walker::new(options, &take_children, |node, depth| {
+        filtered.push(node, depth);
+        Ok::<_, Infallible>(())
+    });
+    let Ok(()) = walker.walk_node(tree, 0);
+    filtered.finish()
+}
+
+/// An entry reached by [`walk`]. Its node has no children, they are visited after it.
+pub struct WalkEntry<'a> {
+    pub node: &'a Node,
+    /// Path of the walked directory, the root of the tree.
+    pub root: &'a Path,
+    /// Level of the entry, 0 for the root.
+    pub depth: usize,
+}
+
+/// Walk `path` with the same filters as [`scan`], visiting every entry in tree order as soon
+/// as it is reached instead of building the tree in memory. Directory sizes are not computed
+/// (left at 0), since that would need the whole tree to be read before the first entry.
+pub fn walk(
+    path: &Path,
+    options: &ScanOptions,
+    visit: &mut dyn FnMut(&WalkEntry) -> Result<()>,
+) -> Result<()> {
+    let metadata = fs::metadata(path).map_err(|source| TreeGenError::Path {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    let root = build_node(path, root_name(path), Some(metadata));
+    let mut walker = Walker::new(options, &read_disk_entries, |node: Node, depth| {
+        visit(&WalkEntry {
+            node: &node,
+            root: path,
+            depth,
+        })
+    });
+    walker.walk_node(root, 0)
+}
+
+/// Get the name shown for the root of the tree, resolving `.` and `..` to the real directory name.
+fn root_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .or_else(|| {
+            fs::canonicalize(path).ok().and_then(|path| {
+                path.file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+            })
+        })
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+/// Reads the unfiltered entries of a directory reached by a walk.
+type ReadEntries = dyn Fn(&mut Node, &ScanOptions) -> io::Result<Vec<Node>>;
+
+/// Read the entries of a directory on disk. Symlinks are listed as they are, following them
+/// could loop forever.
+fn read_disk_entries(dir: &mut Node, options: &ScanOptions) -> io::Result<Vec<Node>> {
+    Ok(read_sorted_dir(&dir.path, options)?
+        .into_iter()
+        .map(|entry| {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let metadata = fs::symlink_metadata(&path).ok();
+            build_node(&path, name, metadata)
+        })
+        .collect())
+}
+
+/// Take the entries of a directory of an in-memory tree.
+fn take_children(dir: &mut Node, _: &ScanOptions) -> io::Result<Vec<Node>> {
+    Ok(mem::take(&mut dir.children))
+}
+
+/// Walks a tree with the filters and depth of a scan, visiting each kept entry in tree order
+/// with its level. Every way of reading a tree goes through here, so they share the same rules.
+struct Walker<'a, F> {
+    options: &'a ScanOptions,
+    read_entries: &'a ReadEntries,
+    visit: F,
+    /// Directories reached but not visited yet. When filtering by extension a directory is
+    /// only visited once an entry inside it is, so directories without matches are dropped.
+    pending: Vec<(Node, usize)>,
+}
+
+impl<'a, F, E> Walker<'a, F>
+where
+    F: FnMut(Node, usize) -> result::Result<(), E>,
+{
+    fn new(options: &'a ScanOptions, read_entries: &'a ReadEntries, visit: F) -> Self {
+        Walker {
+            options,
+            read_entries,
+            visit,
+            pending: vec![],
+        }
+    }
+
+    /// Visit a node, then walk its entries, where `depth` is the level of the node.
+    /// A directory that cannot be read keeps the reason in its `error` instead of children.
+    fn walk_node(&mut self, mut node: Node, depth: usize) -> result::Result<(), E> {
+        let within_depth = self.options.max_depth.is_none_or(|max| depth < max);
+        let entries = match node.is_dir() && within_depth {
+            true => match (self.read_entries)(&mut node, self.options) {
+                Ok(entries) => entries,
+                Err(err) => {
+                    node.error = Some(format!("error opening dir: {}", err));
+                    vec![]
+                }
+            },
+            false => vec![],
+        };
+
+        // Unreadable directories are visited so the error is still reported
+        let deferred = depth > 0
+            && node.is_dir()
+            && node.error.is_none()
+            && self.options.file_extension.is_some();
+        // The entries beyond the depth limit are not walked, so look for a match directly
+        if deferred
+            && !within_depth
+            && !contains_matching_files(&mut node, self.options, self.read_entries)
+        {
+            return Ok(());
+        }
+        // Entries beyond the depth limit are not walked, in-memory trees still have them
+        node.children.clear();
+
+        let index = self.pending.len();
+        let pending = deferred && within_depth;
+        match pending {
+            true => self.pending.push((node, depth)),
+            false => {
+                self.visit_pending()?;
+                (self.visit)(node, depth)?;
+            }
+        }
+
+        for entry in entries {
+            if matches_entry_filters(&entry, self.options) {
+                self.walk_node(entry, depth + 1)?;
+            }
+        }
+
+        // Drop this directory when nothing inside it was visited
+        if pending {
+            self.pending.truncate(index);
+        }
+        Ok(())
+    }
+
+    /// Visit the directories leading to the entry about to be visited.
+    fn visit_pending(&mut self) -> result::Result<(), E> {
+        for (node, depth) in self.pending.drain(..) {
+            (self.visit)(node, depth)?;
+        }
+        Ok(())
+    }
+}
+
+/// Rebuilds a tree from the entries of a walk, visited in tree order.
+struct TreeCollector {
+    /// The entries leading to the last visited one, from the root.
+    stack: Vec<Node>,
+    /// Add the size of each entry to its directory once the entry is complete.
+    sum_sizes: bool,
+}
+
+impl TreeCollector {
+    fn new(sum_sizes: bool) -> Self {
+        TreeCollector {
+            stack: vec![],
+            sum_sizes,
+        }
+    }
+
+    fn push(&mut self, node: Node, depth: usize) {
+        self.close(depth.max(1));
+        self.stack.push(node);
+    }
+
+    /// Attach the entries deeper than `depth` to their parents.
+    fn close(&mut self, depth: usize) {
+        while self.stack.len() > depth {
+            let node = self.stack.pop().unwrap();
+            let parent = self.stack.last_mut().unwrap();
+            if self.sum_sizes {
+                parent.size += node.size;
+            }
+            parent.children.push(node);
+        }
+    }
+
+    fn finish(mut self) -> Node {
+        self.close(1);
+        self.stack.pop().unwrap()
+    }
+}
+
+/// Read the entries of a directory, sorted by file name or newest first.
+fn read_sorted_dir(path: &Path, options: &ScanOptions) -> io::Result<Vec<DirEntry>> {
+    let mut entries: Vec<_> = fs::read_dir(path)?.flatten().collect();
+    entries.sort_by_key(|entry| entry.file_name());
+    if options.sort == SortOrder::Time {
+        // The sort is stable, so entries with the same time stay sorted by name
+        entries.sort_by_cached_key(|entry| {
+            Reverse(
+                entry
+                    .metadata()
+                    .ok()
+                    .and_then(|meta| options.time_field.of_metadata(&meta)),
+            )
+        });
+    }
+    Ok(entries)
+}
+
+/// Check if a directory contains a file passing the filters, at any depth.
+fn contains_matching_files(dir: &mut Node, options: &ScanOptions, read: &ReadEntries) -> bool {
+    let Ok(entries) = read(dir, options) else {
+        return false;
+    };
+
+    entries.into_iter().any(|mut entry| {
+        matches_entry_filters(&entry, options)
+            && (!entry.is_dir() || contains_matching_files(&mut entry, options, read))
+    })
+}
+
+fn build_node(path: &Path, name: String, metadata: Option<Metadata>) -> Node {
+    let kind = metadata.as_ref().map_or(NodeKind::File, |meta| {
+        NodeKind::from_file_type(meta.file_type())
+    });
+
+    Node {
+        name,
+        path: path.to_path_buf(),
+        kind,
+        // Directory sizes are filled in by the scan
+        size: match kind {
+            NodeKind::Directory => 0,
+            _ => metadata.as_ref().map_or(0, Metadata::len),
+        },
+        mode: metadata.as_ref().map(|meta| meta.permissions().mode()),
+        modified: metadata.as_ref().and_then(|meta| meta.modified().ok()),
+        accessed: metadata.as_ref().and_then(|meta| meta.accessed().ok()),
+        changed: metadata
+            .as_ref()
+            .and_then(|meta| TimeField::Changed.of_metadata(meta)),
+        created: metadata.as_ref().and_then(|meta| meta.created().ok()),
+        uid: metadata.as_ref().map(MetadataExt::uid),
+        gid: metadata.as_ref().map(MetadataExt::gid),
+        inode: metadata.as_ref().map(MetadataExt::ino),
+        nlink: metadata.as_ref().map(MetadataExt::nlink),
+        link_target: match kind {
+            NodeKind::Symlink => fs::read_link(path).ok(),
+            _ => None,
+        },
+        error: None,
+        children: vec![],
+    }
+}
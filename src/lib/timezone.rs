@@ -0,0 +1,710 @@
+use std::{
+    env, fs,
+    path::{Component, Path, PathBuf},
+};
+
+use super::date::{day_of_week, days_from_civil, days_in_month, year_of_timestamp};
+
+/// Where the time zone database is, unless `TZDIR` says otherwise.
+const ZONEINFO_DIR: &str = "/usr/share/zoneinfo";
+
+/// Offset from UTC and abbreviation of the local time at some instant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalTimeType {
+    /// Seconds east of UTC.
+    pub offset: i32,
+    pub is_dst: bool,
+    pub abbreviation: String,
+}
+
+/// A time zone read from the TZif files of the time zone database, like `/etc/localtime`.
+#[derive(Debug, Clone)]
+pub struct TimeZone {
+    /// UTC instants the local time type changes at, in ascending order.
+    transitions: Vec<i64>,
+    /// Index in `types` of the local time type starting at each transition.
+    transition_types: Vec<usize>,
+    types: Vec<LocalTimeType>,
+    /// Rule for the instants after the last transition, from the footer of the file.
+    rule: Option<PosixRule>,
+}
+
+impl TimeZone {
+    pub fn utc() -> Self {
+        TimeZone {
+            transitions: vec![],
+            transition_types: vec![],
+            types: vec![LocalTimeType {
+                offset: 0,
+                is_dst: false,
+                abbreviation: "UTC".to_string(),
+            }],
+            rule: None,
+        }
+    }
+
+    /// Get the local time zone, from `TZ` like the C library does or from `/etc/localtime`.
+    /// Falls back to UTC when the zone cannot be found or read.
+    pub fn local() -> Self {
+        let zone = match env::var("TZ") {
+            Ok(tz) => TimeZone::from_tz(&tz),
+            Err(_) => TimeZone::from_file(Path::new("/etc/localtime")),
+        };
+        zone.unwrap_or_else(TimeZone::utc)
+    }
+
+    /// Get a zone by its value of `TZ`: a zone name like `Europe/Berlin`, a path to a TZif
+    /// file or a POSIX TZ string like `CET-1CEST,M3.5.0,M10.5.0/3`.
+    pub fn from_tz(tz: &str) -> Option<Self> {
+        let tz = tz.strip_prefix(':').unwrap_or(tz);
+        if tz.is_empty() {
+            return Some(TimeZone::utc());
+        }
+        if tz.starts_with('/') {
+            return TimeZone::from_file(Path::new(tz));
+        }
+
+        // Names must stay inside the database
+        let name = Path::new(tz);
+        if name
+            .components()
+            .all(|part| matches!(part, Component::Normal(_)))
+        {
+            let dir = env::var_os("TZDIR")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| PathBuf::from(ZONEINFO_DIR));
+            if let Some(zone) = TimeZone::from_file(&dir.join(name)) {
+                return Some(zone);
+            }
+        }
+        PosixRule::parse(tz).map(TimeZone::from_rule)
+    }
+
+    /// Read a TZif file, `None` when it cannot be read or is not one.
+    pub fn from_file(path: &Path) -> Option<Self> {
+        TimeZone::from_tzif(&fs::read(path).ok()?)
+    }
+
+    /// Parse the contents of a TZif file, as described by RFC 8536. The 64-bit data of
+    /// version 2 and later files is used when present.
+    pub fn from_tzif(data: &[u8]) -> Option<Self> {
+        let mut reader = TzifReader { data, position: 0 };
+        let (version, counts) = reader.header()?;
+        if version == 0 {
+            return reader.data_block(counts, 4);
+        }
+
+        // Skip the 32-bit data, newer versions repeat it with 64-bit times
+        reader.skip(counts.block_len(4))?;
+        let (_, counts) = reader.header()?;
+        let mut zone = reader.data_block(counts, 8)?;
+        zone.rule = reader.footer().and_then(PosixRule::parse);
+        Some(zone)
+    }
+
+    fn from_rule(rule: PosixRule) -> Self {
+        TimeZone {
+            transitions: vec![],
+            transition_types: vec![],
+            types: vec![rule.std.clone()],
+            rule: Some(rule),
+        }
+    }
+
+    /// Get the UTC time of a local time, both in seconds since the UNIX epoch. Local times
+    /// that are skipped or repeated when the offset changes resolve to one of their instants.
+    pub fn utc_from_local(&self, local: i64) -> i64 {
+        let guess = local - self.local_time_type(local).offset as i64;
+        local - self.local_time_type(guess).offset as i64
+    }
+
+    /// Get the local time type at `secs` seconds since the UNIX epoch.
+    pub fn local_time_type(&self, secs: i64) -> LocalTimeType {
+        let after = self
+            .transitions
+            .partition_point(|&transition| transition <= secs);
+        if after == self.transitions.len() {
+            if let Some(rule) = &self.rule {
+                return rule.local_time_type(secs);
+            }
+        }
+        // Instants before the first transition use the first type
+        let index = match after {
+            0 => 0,
+            after => self.transition_types[after - 1],
+        };
+        self.types.get(index).cloned().unwrap_or(LocalTimeType {
+            offset: 0,
+            is_dst: false,
+            abbreviation: "UTC".to_string(),
+        })
+    }
+}
+
+/// Number of records of each kind in a TZif data block.
+#[derive(Clone, Copy)]
+struct TzifCounts {
+    isutcnt: usize,
+    isstdcnt: usize,
+    leapcnt: usize,
+    timecnt: usize,
+    typecnt: usize,
+    charcnt: usize,
+}
+
+impl TzifCounts {
+    fn block_len(&self, time_size: usize) -> usize {
+        self.timecnt * time_size
+            + self.timecnt
+            + self.typecnt * 6
+            + self.charcnt
+            + self.leapcnt * (time_size + 4)
+            + self.isstdcnt
+            + self.isutcnt
+    }
+}
+
+struct TzifReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> TzifReader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.position..self.position.checked_add(len)?)?;
+        self.position += len;
+        Some(bytes)
+    }
+
+    fn skip(&mut self, len: usize) -> Option<()> {
+        self.take(len).map(|_| ())
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_be_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn time(&mut self, size: usize) -> Option<i64> {
+        let bytes = self.take(size)?;
+        match size {
+            4 => Some(i32::from_be_bytes(bytes.try_into().ok()?) as i64),
+            _ => Some(i64::from_be_bytes(bytes.try_into().ok()?)),
+        }
+    }
+
+    fn header(&mut self) -> Option<(u8, TzifCounts)> {
+        if self.take(4)? != b"TZif" {
+            return None;
+        }
+        let version = match self.take(1)?[0] {
+            0 => 0,
+            version => version.checked_sub(b'0')?,
+        };
+        self.skip(15)?;
+
+        let mut count = || self.u32().map(|count| count as usize);
+        let counts = TzifCounts {
+            isutcnt: count()?,
+            isstdcnt: count()?,
+            leapcnt: count()?,
+            timecnt: count()?,
+            typecnt: count()?,
+            charcnt: count()?,
+        };
+        Some((version, counts))
+    }
+
+    fn data_block(&mut self, counts: TzifCounts, time_size: usize) -> Option<TimeZone> {
+        let transitions = (0..counts.timecnt)
+            .map(|_| self.time(time_size))
+            .collect::<Option<Vec<_>>>()?;
+        let transition_types: Vec<usize> = self
+            .take(counts.timecnt)?
+            .iter()
+            .map(|&index| index as usize)
+            .collect();
+
+        let mut raw_types = vec![];
+        for _ in 0..counts.typecnt {
+            let offset = self.u32()? as i32;
+            let flags = self.take(2)?;
+            raw_types.push((offset, flags[0] != 0, flags[1] as usize));
+        }
+        let abbreviations = self.take(counts.charcnt)?;
+        let types = raw_types
+            .into_iter()
+            .map(|(offset, is_dst, start)| {
+                let rest = abbreviations.get(start..).unwrap_or_default();
+                let end = rest
+                    .iter()
+                    .position(|&byte| byte == 0)
+                    .unwrap_or(rest.len());
+                LocalTimeType {
+                    offset,
+                    is_dst,
+                    abbreviation: String::from_utf8_lossy(&rest[..end]).into_owned(),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // Leap seconds and the standard/UT indicators are not needed to convert times
+        self.skip(counts.leapcnt * (time_size + 4) + counts.isstdcnt + counts.isutcnt)?;
+
+        if types.is_empty() || transition_types.iter().any(|&index| index >= types.len()) {
+            return None;
+        }
+        Some(TimeZone {
+            transitions,
+            transition_types,
+            types,
+            rule: None,
+        })
+    }
+
+    fn footer(&mut self) -> Option<&'a str> {
+        let rest = self.data.get(self.position..)?.strip_prefix(b"\n")?;
+        let end = rest.iter().position(|&byte| byte == b'\n')?;
+        std::str::from_utf8(&rest[..end])
+            .ok()
+            .filter(|rule| !rule.is_empty())
+    }
+}
+
+/// A POSIX TZ string, like `CET-1CEST,M3.5.0,M10.5.0/3`.
+#[derive(Debug, Clone)]
+struct PosixRule {
+    std: LocalTimeType,
+    dst: Option<(LocalTimeType, TransitionRule, TransitionRule)>,
+}
+
+/// The local date and time daylight saving time starts or ends at in a year.
+#[derive(Debug, Clone, Copy)]
+struct TransitionRule {
+    date: TransitionDate,
+    /// Seconds after the local midnight of the date, may be negative or past a day.
+    time: i64,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum TransitionDate {
+    /// `Jn`: day 1 to 365 of the year, February 29 is never counted.
+    Julian(u16),
+    /// `n`: day 0 to 365 of the year, February 29 is counted.
+    ZeroBased(u16),
+    /// `Mm.w.d`: day `d` (0 is Sunday) of week `w` (5 is the last) of month `m`.
+    MonthWeekDay(u32, u32, u32),
+}
+
+impl PosixRule {
+    fn parse(rule: &str) -> Option<Self> {
+        let mut parser = RuleParser { rest: rule };
+        let std_name = parser.name()?;
+        let std_offset = -parser.offset()?;
+        let std = LocalTimeType {
+            offset: std_offset,
+            is_dst: false,
+            abbreviation: std_name,
+        };
+        if parser.rest.is_empty() {
+            return Some(PosixRule { std, dst: None });
+        }
+
+        let dst_name = parser.name()?;
+        let dst_offset = match parser.rest.starts_with(',') || parser.rest.is_empty() {
+            true => std_offset.checked_add(3_600)?,
+            false => -parser.offset()?,
+        };
+        let dst = LocalTimeType {
+            offset: dst_offset,
+            is_dst: true,
+            abbreviation: dst_name,
+        };
+
+        // Zones without rules follow the current US rules, like the C library assumes
+        let (start, end) = match parser.rest.is_empty() {
+            true => (
+                TransitionRule {
+                    date: TransitionDate::MonthWeekDay(3, 2, 0),
+                    time: 7_200,
+                },
+                TransitionRule {
+                    date: TransitionDate::MonthWeekDay(11, 1, 0),
+                    time: 7_200,
+                },
+            ),
+            false => {
+                parser.rest = parser.rest.strip_prefix(',')?;
+                let start = parser.transition()?;
+                parser.rest = parser.rest.strip_prefix(',')?;
+                (start, parser.transition()?)
+            }
+        };
+        if !parser.rest.is_empty() {
+            return None;
+        }
+        Some(PosixRule {
+            std,
+            dst: Some((dst, start, end)),
+        })
+    }
+
+    fn local_time_type(&self, secs: i64) -> LocalTimeType {
+        let Some((dst, start, end)) = &self.dst else {
+            return self.std.clone();
+        };
+
+        // Transitions are given in the local time in effect before them
+        let year = year_of_timestamp(secs + self.std.offset as i64);
+        let starts = start.timestamp(year) - self.std.offset as i64;
+        let ends = end.timestamp(year) - dst.offset as i64;
+        let in_dst = match starts <= ends {
+            true => starts <= secs && secs < ends,
+            // Southern hemisphere, daylight saving time spans the new year
+            false => secs < ends || starts <= secs,
+        };
+        match in_dst {
+            true => dst.clone(),
+            false => self.std.clone(),
+        }
+    }
+}
+
+impl TransitionRule {
+    /// Get the local time of the transition in `year`, as seconds since the UNIX epoch.
+    fn timestamp(&self, year: i32) -> i64 {
+        let day = match self.date {
+            TransitionDate::Julian(day) => {
+                let leap_day = days_in_month(year, 2) == 29 && day >= 60;
+                days_from_civil(year, 1, 1) + day as i64 - 1 + leap_day as i64
+            }
+            TransitionDate::ZeroBased(day) => days_from_civil(year, 1, 1) + day as i64,
+            TransitionDate::MonthWeekDay(month, week, weekday) => {
+                let first = days_from_civil(year, month, 1);
+                let first_match = (weekday as i64 - day_of_week(first)).rem_euclid(7);
+                let mut day = first_match + 7 * (week as i64 - 1);
+                // Week 5 means the last one, which may be the fourth
+                while day >= days_in_month(year, month) as i64 {
+                    day -= 7;
+                }
+                first + day
+            }
+        };
+        day * 86_400 + self.time
+    }
+}
+
+struct RuleParser<'a> {
+    rest: &'a str,
+}
+
+impl RuleParser<'_> {
+    /// Parse a zone abbreviation, either letters or anything between `<` and `>`.
+    fn name(&mut self) -> Option<String> {
+        let (name, rest) = match self.rest.strip_prefix('<') {
+            Some(quoted) => {
+                let (name, rest) = quoted.split_once('>')?;
+                (name, rest)
+            }
+            None => {
+                let end = self
+                    .rest
+                    .find(|c: char| !c.is_ascii_alphabetic())
+                    .unwrap_or(self.rest.len());
+                self.rest.split_at(end)
+            }
+        };
+        if name.len() < 3 {
+            return None;
+        }
+        self.rest = rest;
+        Some(name.to_string())
+    }
+
+    /// Parse `[+-]hh[:mm[:ss]]` into seconds. Hours go up to 167, like RFC 8536 allows for
+    /// the times of transitions.
+    fn offset(&mut self) -> Option<i32> {
+        let sign = match self.rest.chars().next()? {
+            '-' => -1,
+            _ => 1,
+        };
+        self.rest = self.rest.trim_start_matches(['+', '-']);
+
+        let end = self
+            .rest
+            .find(|c: char| !c.is_ascii_digit() && c != ':')
+            .unwrap_or(self.rest.len());
+        let (time, rest) = self.rest.split_at(end);
+        let mut seconds: i32 = 0;
+        for (i, part) in time.split(':').enumerate() {
+            let limit = [167, 59, 59].get(i)?;
+            let number = part.parse::<i32>().ok().filter(|number| number <= limit)?;
+            seconds = seconds.checked_add(number.checked_mul([3_600, 60, 1][i])?)?;
+        }
+        self.rest = rest;
+        Some(sign * seconds)
+    }
+
+    fn transition(&mut self) -> Option<TransitionRule> {
+        let end = self.rest.find([',', '/']).unwrap_or(self.rest.len());
+        let (date, rest) = self.rest.split_at(end);
+        self.rest = rest;
+
+        let date = if let Some(day) = date.strip_prefix('J') {
+            TransitionDate::Julian(day.parse().ok().filter(|day| (1..=365).contains(day))?)
+        } else if let Some(rule) = date.strip_prefix('M') {
+            let mut parts = rule.split('.').map(|part| part.parse::<u32>().ok());
+            let (month, week, weekday) = (parts.next()??, parts.next()??, parts.next()??);
+            if parts.next().is_some()
+                || !(1..=12).contains(&month)
+                || !(1..=5).contains(&week)
+                || weekday > 6
+            {
+                return None;
+            }
+            TransitionDate::MonthWeekDay(month, week, weekday)
+        } else {
+            TransitionDate::ZeroBased(date.parse().ok().filter(|day| *day <= 365)?)
+        };
+
+        let time = match self.rest.strip_prefix('/') {
+            Some(rest) => {
+                self.rest = rest;
+                self.offset()? as i64
+            }
+            None => 7_200,
+        };
+        Some(TransitionRule { date, time })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BERLIN: &str = "CET-1CEST,M3.5.0,M10.5.0/3";
+
+    fn offset_at(zone: &TimeZone, secs: i64) -> (i32, bool, String) {
+        let local = zone.local_time_type(secs);
+        (local.offset, local.is_dst, local.abbreviation)
+    }
+
+    fn rule_zone(rule: &str) -> TimeZone {
+        TimeZone::from_rule(PosixRule::parse(rule).unwrap())
+    }
+
+    #[test]
+    fn follows_posix_rules() {
+        let cases = [
+            // Last Sundays of March and October, switching at 01:00 UTC
+            (BERLIN, 1711846799, 3_600, false, "CET"),
+            (BERLIN, 1711846800, 7_200, true, "CEST"),
+            (BERLIN, 1729990799, 7_200, true, "CEST"),
+            (BERLIN, 1729990800, 3_600, false, "CET"),
+            (BERLIN, -15897600, 7_200, true, "CEST"),
+            // US rules, also assumed when a zone with daylight saving time has no rules
+            ("EST5EDT,M3.2.0,M11.1.0", 1710053999, -18_000, false, "EST"),
+            ("EST5EDT,M3.2.0,M11.1.0", 1710054000, -14_400, true, "EDT"),
+            ("EST5EDT", 1730613599, -14_400, true, "EDT"),
+            ("EST5EDT", 1730613600, -18_000, false, "EST"),
+            // Southern hemisphere, daylight saving time spans the new year
+            (
+                "AEST-10AEDT,M10.1.0,M4.1.0/3",
+                1712419199,
+                39_600,
+                true,
+                "AEDT",
+            ),
+            (
+                "AEST-10AEDT,M10.1.0,M4.1.0/3",
+                1712419200,
+                36_000,
+                false,
+                "AEST",
+            ),
+            (
+                "AEST-10AEDT,M10.1.0,M4.1.0/3",
+                1728143999,
+                36_000,
+                false,
+                "AEST",
+            ),
+            (
+                "AEST-10AEDT,M10.1.0,M4.1.0/3",
+                1728144000,
+                39_600,
+                true,
+                "AEDT",
+            ),
+            (
+                "<-04>4<-03>,M9.1.6/24,M4.1.6/24",
+                1719792000,
+                -14_400,
+                false,
+                "-04",
+            ),
+            (
+                "<-04>4<-03>,M9.1.6/24,M4.1.6/24",
+                1704067200,
+                -10_800,
+                true,
+                "-03",
+            ),
+            // Julian days never count February 29, zero-based days do
+            ("AAA0BBB,J60/0,J61/0", 1709208000, 0, false, "AAA"),
+            ("AAA0BBB,J60/0,J61/0", 1709294400, 3_600, true, "BBB"),
+            ("AAA0BBB,59/0,60/0", 1709208000, 3_600, true, "BBB"),
+            ("AAA0BBB,59/0,60/0", 1677672000, 3_600, true, "BBB"),
+            ("<+0330>-3:30", 0, 12_600, false, "+0330"),
+            ("UTC0", 0, 0, false, "UTC"),
+        ];
+        for (rule, secs, offset, is_dst, abbreviation) in cases {
+            assert_eq!(
+                offset_at(&rule_zone(rule), secs),
+                (offset, is_dst, abbreviation.to_string()),
+                "{} at {}",
+                rule,
+                secs
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        for rule in [
+            "",
+            "AB1",
+            "ABC",
+            "ABC9999999",
+            "ABC-168",
+            "ABC1:60",
+            "ABC1:00:00:00",
+            "ABC2147483647",
+            "ABC1DEF,M3.5.0",
+            "ABC1DEF,M13.1.0,M10.5.0",
+            "ABC1DEF,M3.6.0,M10.5.0",
+            "ABC1DEF,M3.5.7,M10.5.0",
+            "ABC1DEF,J0,J365",
+            "ABC1DEF,0,366",
+            "ABC1DEF,M3.5.0,M10.5.0/999999999999",
+            "ABC1DEF,M3.5.0,M10.5.0 trailing",
+        ] {
+            assert!(PosixRule::parse(rule).is_none(), "{:?} was parsed", rule);
+        }
+        assert!(PosixRule::parse("ABC-167:59:59").is_some());
+    }
+
+    #[test]
+    fn resolves_skipped_and_repeated_local_times() {
+        let zone = rule_zone(BERLIN);
+        // 2024-03-31 02:30 is skipped, 2024-10-27 02:30 happens twice
+        let skipped = 1711852200;
+        assert!([skipped - 3_600, skipped - 7_200].contains(&zone.utc_from_local(skipped)));
+        let repeated = 1729996200;
+        let utc = zone.utc_from_local(repeated);
+        assert!([repeated - 3_600, repeated - 7_200].contains(&utc));
+        assert_eq!(utc + zone.local_time_type(utc).offset as i64, repeated);
+        // Other local times convert back to themselves
+        for local in [0, 1704067200, 1719792000, -15897600] {
+            let utc = zone.utc_from_local(local);
+            assert_eq!(utc + zone.local_time_type(utc).offset as i64, local);
+        }
+    }
+
+    /// Write a TZif file with the given transitions, local time types and footer, in the
+    /// 32-bit format for version 1 and followed by the 64-bit data for later versions.
+    fn tzif(
+        version: u8,
+        transitions: &[(i64, u8)],
+        types: &[(i32, bool, &str)],
+        footer: &str,
+    ) -> Vec<u8> {
+        let mut abbreviations = vec![];
+        let mut starts = vec![];
+        for (_, _, name) in types {
+            starts.push(abbreviations.len() as u8);
+            abbreviations.extend_from_slice(name.as_bytes());
+            abbreviations.push(0);
+        }
+        let block = |data: &mut Vec<u8>, version: u8, time_size: usize| {
+            data.extend_from_slice(b"TZif");
+            data.push(version);
+            data.extend_from_slice(&[0; 15]);
+            let counts = [0, 0, 0, transitions.len(), types.len(), abbreviations.len()];
+            for count in counts {
+                data.extend_from_slice(&(count as u32).to_be_bytes());
+            }
+            for (time, _) in transitions {
+                match time_size {
+                    4 => data.extend_from_slice(&(*time as i32).to_be_bytes()),
+                    _ => data.extend_from_slice(&time.to_be_bytes()),
+                }
+            }
+            data.extend(transitions.iter().map(|(_, index)| index));
+            for ((offset, is_dst, _), start) in types.iter().zip(&starts) {
+                data.extend_from_slice(&offset.to_be_bytes());
+                data.extend_from_slice(&[*is_dst as u8, *start]);
+            }
+            data.extend_from_slice(&abbreviations);
+        };
+
+        let mut data = vec![];
+        block(&mut data, version, 4);
+        if version != 0 {
+            block(&mut data, version, 8);
+            data.extend_from_slice(format!("\n{}\n", footer).as_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn reads_tzif_files() {
+        let types = [(3_600, false, "CET"), (7_200, true, "CEST")];
+        let transitions = [(1711846800, 1), (1729990800, 0)];
+        let cases = [
+            // Before the first transition, between them and after them
+            (1700000000, 3_600, false, "CET"),
+            (1711846800, 7_200, true, "CEST"),
+            (1729990800, 3_600, false, "CET"),
+        ];
+
+        for version in [0, b'2', b'3'] {
+            let zone = TimeZone::from_tzif(&tzif(version, &transitions, &types, BERLIN)).unwrap();
+            for (secs, offset, is_dst, abbreviation) in cases {
+                assert_eq!(
+                    offset_at(&zone, secs),
+                    (offset, is_dst, abbreviation.to_string())
+                );
+            }
+            // Only version 2 and later files have a rule for later years
+            let summer = match version {
+                0 => (3_600, false, "CET".to_string()),
+                _ => (7_200, true, "CEST".to_string()),
+            };
+            assert_eq!(offset_at(&zone, 1751328000), summer);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_tzif_files() {
+        let types = [(3_600, false, "CET")];
+        let valid = tzif(b'2', &[(0, 0)], &types, "CET-1");
+        let bad_index = tzif(b'2', &[(0, 1)], &types, "CET-1");
+        let mut bad_version = valid.clone();
+        bad_version[4] = 1;
+
+        for data in [
+            vec![],
+            b"TZif".to_vec(),
+            b"TZjf2".to_vec(),
+            bad_version,
+            bad_index,
+            valid[..valid.len() / 2].to_vec(),
+            tzif(b'2', &[], &[], "CET-1"),
+        ] {
+            assert!(TimeZone::from_tzif(&data).is_none());
+        }
+        assert!(TimeZone::from_tzif(&valid).is_some());
+    }
+}
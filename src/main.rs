@@ -1,12 +1,30 @@
-use clap::{Arg, ArgAction, Command};
+use clap::{builder::PossibleValuesParser, value_parser, Arg, ArgAction, ArgMatches, Command};
 use colored::Colorize;
-use std::{env, fs, path::Path};
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, Read, Write},
+    path::Path,
+    process,
+};
 
-// My Library modules
-mod lib;
-use lib::{
-    compare::compare_directories,
-    generate::{generate_json_tree, generate_tree},
+use tree_gen::{
+    compare::{
+        compare_directories, compare_directories_three_way, compare_with_text_tree, Attribute,
+        CompareBy, CompareOptions, TreeSource,
+    },
+    date::{TimeFormat, DEFAULT_TIME_FORMAT},
+    error::{Result, TreeGenError},
+    output_file::open_output,
+    parse::parse_tree,
+    render::{
+        csv::Column, diff_renderer_names, get_diff_renderer, get_merge_renderer, get_renderer,
+        json::TreeNode, renderer_names, BranchStyle, RenderOptions,
+    },
+    restore::{restore, RestoreOptions},
+    scaffold,
+    scan::{NodeKind, SortOrder, TimeField},
+    timezone::TimeZone,
+    TreeGen,
 };
 
 /// Simple CLI tool to generate folder structure in ASCII for markdown files.
@@ -16,25 +34,116 @@ fn main() {
         .version("0.1.3")
         .about("tree_gen is a CLI tool to generate folder structure in ASCII, JSON and visualize folder structure with nice and easy way without leaving your terminal")
         .author("Kei-K23")
+        .args_conflicts_with_subcommands(true)
+        .subcommand_negates_reqs(true)
+        .subcommand(
+            Command::new("restore")
+                .about("Recreate the directory and file skeleton recorded in a JSON snapshot")
+                .arg(
+                    Arg::new("snapshot")
+                        .help("JSON file written by --format json")
+                        .value_name("SNAPSHOT")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("dest")
+                        .help("Directory to recreate the snapshot in")
+                        .value_name("DEST")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("fill")
+                        .help("Fill files with zeros up to their recorded size instead of leaving them empty")
+                        .long("fill")
+                        .required(false)
+                        .num_args(0)
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("scaffold")
+                .about("Create the directories and empty files drawn in a text tree")
+                .arg(
+                    Arg::new("input")
+                        .help("File with the tree, as the ascii output draws it, or - to read from stdin")
+                        .value_name("INPUT")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("dest")
+                        .help("Directory that stands for the root of the tree")
+                        .value_name("DEST")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("dry_run")
+                        .help("Print what would be created without touching the filesystem")
+                        .short('n')
+                        .long("dry-run")
+                        .required(false)
+                        .num_args(0)
+                        .action(ArgAction::SetTrue),
+                ),
+        )
         .arg(
             Arg::new("path")
-                .help("Path of the directory to display or compare")
+                .help("Path of the directory to display, or of the directory or JSON snapshot to compare")
                 .value_name("PATH")
                 .required(true),
         )
           .arg(
             Arg::new("compare")
-                .help("Path of a second directory to compare against")
+                .help("Path of a second directory, of a JSON snapshot or of a text tree saved from the ascii output, to compare against")
                 .long("compare")
                 .value_name("COMPARE_PATH"),
         )
+        .arg(
+            Arg::new("compare_format")
+                .help("Output format of the comparison")
+                .long("compare-format")
+                .value_name("FORMAT")
+                .default_value("text")
+                .value_parser(PossibleValuesParser::new(diff_renderer_names())),
+        )
+        .arg(
+            Arg::new("base")
+                .help("Base directory both compared directories come from, for a three-way comparison where PATH is ours and COMPARE_PATH is theirs")
+                .long("base")
+                .value_name("BASE_PATH")
+                .requires("compare"),
+        )
+        .arg(
+            Arg::new("compare_by")
+                .help("What decides that two files of a comparison differ: size, modification time, content hash or a byte by byte comparison")
+                .long("compare-by")
+                .value_name("MODE")
+                .default_value("size")
+                .value_parser(PossibleValuesParser::new(CompareBy::ALL.map(|mode| mode.name()))),
+        )
+        .arg(
+            Arg::new("compare_attributes")
+                .help("Comma separated metadata compared besides the contents: mode, owner, group or mtime")
+                .long("compare-attributes")
+                .value_name("ATTRIBUTES")
+                .value_delimiter(',')
+                .value_parser(PossibleValuesParser::new(Attribute::ALL.map(|attribute| attribute.name()))),
+        )
+        .arg(
+            Arg::new("detect_renames")
+                .help("Report removed and added files with the same contents as renamed or moved")
+                .long("detect-renames")
+                .required(false)
+                .num_args(0)
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("depth")
                 .help("Maximum depth of the tree")
                 .short('d')
                 .long("depth")
                 .value_name("DEPTH")
-                .default_value("10"),
+                .default_value("10")
+                .value_parser(value_parser!(usize)),
         )
         .arg(
             Arg::new("file_extension")
@@ -63,24 +172,43 @@ fn main() {
                 .help("Limit preview lines for files")
                 .long("preview-lines")
                 .value_name("LINES")
+                .value_parser(value_parser!(usize)),
         )
         .arg(
             Arg::new("date_filter")
-                 .help("Filter files by date. Format: <before|after|between> <date1>[,<date2>]")
+                 .help("Filter files by date, the modification time unless --time picks another: before, after, since, until or on <time>, between <time> and <time>, newer-than or older-than <duration> like 7d or 2w, or an interval like [2024-01-01, 2024-02-01)")
                 .long("date-filter")
                 .value_name("DATE_FILTER")
         )
+        .arg(
+            Arg::new("time")
+                .help("Timestamp used by the date filter, the time order and the dates of the xml and html output: modification, access, status change or birth time")
+                .long("time")
+                .value_name("FIELD")
+                .value_parser(PossibleValuesParser::new(TimeField::ALL.map(|field| field.name())))
+                .default_value("mtime"),
+        )
+        .arg(
+            Arg::new("sort")
+                .help("Order of the entries of each directory, time lists the newest first")
+                .long("sort")
+                .value_name("ORDER")
+                .value_parser(PossibleValuesParser::new(SortOrder::ALL.map(|order| order.name())))
+                .default_value("name"),
+        )
         .arg(
             Arg::new("size_min")
                  .help("Minimum file size in bytes for filtering")
                 .long("size-min")
                 .value_name("SIZE_MIN")
+                .value_parser(value_parser!(u64)),
         )
         .arg(
             Arg::new("size_max")
             .help("Maximum file size in bytes for filtering")
             .long("size-max")
             .value_name("SIZE_MAX")
+            .value_parser(value_parser!(u64)),
         )
         .arg(
             Arg::new("include")
@@ -90,9 +218,10 @@ fn main() {
         )
          .arg(
             Arg::new("exclude")
-                .help("Exclude files matching this pattern (wildcard or regex)")
+                .help("Exclude files and directories matching this pattern (wildcard or regex), can be repeated")
                 .long("exclude")
-                .value_name("EXCLUDE"),
+                .value_name("EXCLUDE")
+                .action(ArgAction::Append),
         )
         .arg(
             Arg::new("ignore_hidden")
@@ -120,104 +249,366 @@ fn main() {
                 .num_args(0)
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("format")
+                .help("Output format of the directory structure")
+                .short('f')
+                .long("format")
+                .value_name("FORMAT")
+                .default_value("ascii")
+                .value_parser(PossibleValuesParser::new(renderer_names())),
+        )
+        .arg(
+            Arg::new("title")
+                .help("Title written above the tree in the markdown and html output")
+                .long("title")
+                .value_name("TITLE"),
+        )
+        .arg(
+            Arg::new("markdown_links")
+                .help("List entries as relative links instead of a code block in the markdown output")
+                .long("markdown-links")
+                .required(false)
+                .num_args(0)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("human_readable")
+                .help("Add human readable size, type, permission and date strings to the json, yaml and toml output")
+                .long("human-readable")
+                .required(false)
+                .num_args(0)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("time_format")
+                .help("strftime-style format of the dates in the xml, html, csv and human readable structured output, in the local time zone")
+                .long("time-format")
+                .value_name("FORMAT")
+                .default_value(DEFAULT_TIME_FORMAT),
+        )
+        .arg(
+            Arg::new("columns")
+                .help("Comma separated columns of the csv and tsv output")
+                .long("columns")
+                .value_name("COLUMNS")
+                .value_delimiter(',')
+                .value_parser(PossibleValuesParser::new(Column::ALL.map(|column| column.name()))),
+        )
         .arg(
             Arg::new("json")
-                .help("Generate the json output of directory structure")
+                .help("Generate the json output of directory structure (same as --format json)")
                 .short('j')
                 .long("json")
                 .required(false)
                 .num_args(0)
+                .conflicts_with("format")
                 .action(ArgAction::SetTrue),
         )
         .get_matches();
 
+    // Invalid arguments are reported by clap, errors found while running are reported here
+    match run(&matches) {
+        Ok(code) => process::exit(code),
+        Err(err) => {
+            eprintln!("{} {}", "error:".red().bold(), err);
+            // Comparisons exit like `diff`, with 2 on trouble
+            match matches.get_one::<String>("compare") {
+                Some(_) => process::exit(2),
+                None => process::exit(1),
+            }
+        }
+    }
+}
+
+/// Run the command, returning the exit code. Comparisons exit with 0 when the trees are
+/// identical, 1 when they differ and 2 when some entries could not be read. Three-way
+/// comparisons exit with 1 only when there are conflicts.
+fn run(matches: &ArgMatches) -> Result<i32> {
+    match matches.subcommand() {
+        Some(("restore", restore_matches)) => return run_restore(restore_matches).map(|_| 0),
+        Some(("scaffold", scaffold_matches)) => return run_scaffold(scaffold_matches).map(|_| 0),
+        _ => {}
+    }
+
     let path_str = matches.get_one::<String>("path").unwrap();
     let compare_path_str = matches.get_one::<String>("compare");
     let file_extension = matches.get_one::<String>("file_extension");
     let output_file = matches.get_one::<String>("output_file");
     let branch_style = matches.get_one::<String>("branch_style");
-    let preview_lines = matches.get_one::<String>("preview_lines");
-    let depth_str = matches.get_one::<String>("depth").unwrap();
-    let depth_int = depth_str.parse::<usize>().unwrap();
+    let preview_lines = matches.get_one::<usize>("preview_lines");
+    let depth_int = *matches.get_one::<usize>("depth").unwrap();
 
     let ignore_hidden = matches.get_one::<bool>("ignore_hidden").unwrap();
     let icons = matches.get_one::<bool>("icons").unwrap();
     let show_size = matches.get_one::<bool>("show_sizes").unwrap();
     let json = matches.get_one::<bool>("json").unwrap();
+    let title = matches.get_one::<String>("title");
+    let markdown_links = matches.get_one::<bool>("markdown_links").unwrap();
+    let human_readable = matches.get_one::<bool>("human_readable").unwrap();
+    let time_format = matches.get_one::<String>("time_format").unwrap();
+    // The timestamp and the order are validated by clap against the known names
+    let time_field = TimeField::from_name(matches.get_one::<String>("time").unwrap()).unwrap();
+    let sort = SortOrder::from_name(matches.get_one::<String>("sort").unwrap()).unwrap();
+    let columns = matches
+        .get_many::<String>("columns")
+        .map(|columns| {
+            columns
+                .filter_map(|column| Column::from_name(column))
+                .collect()
+        })
+        .unwrap_or_default();
+    let compare_format = matches.get_one::<String>("compare_format").unwrap();
+    // The mode is validated by clap against the known modes
+    let compare_options = CompareOptions {
+        compare_by: CompareBy::from_name(matches.get_one::<String>("compare_by").unwrap()).unwrap(),
+        detect_renames: *matches.get_one::<bool>("detect_renames").unwrap(),
+        attributes: matches
+            .get_many::<String>("compare_attributes")
+            .map(|attributes| {
+                attributes
+                    .filter_map(|attribute| Attribute::from_name(attribute))
+                    .collect()
+            })
+            .unwrap_or_default(),
+    };
+    let format = match json {
+        true => "json",
+        false => matches.get_one::<String>("format").unwrap(),
+    };
 
     let path = Path::new(path_str);
     let compare_path = compare_path_str.map(Path::new);
-    let current_dir_path = env::current_dir().unwrap();
-    let max_depth = Some(depth_int);
+    let base_path = matches.get_one::<String>("base").map(Path::new);
 
     // Get the filter flags
     let date_filter = matches.get_one::<String>("date_filter");
     let include = matches.get_one::<String>("include");
-    let exclude = matches.get_one::<String>("exclude");
-    let size_min = matches
-        .get_one::<String>("size_min")
-        .and_then(|s| s.parse::<u64>().ok());
-    let size_max = matches
-        .get_one::<String>("size_max")
-        .and_then(|s| s.parse::<u64>().ok());
-
-    // Get the name of the root directory for the display
-    let root_dir_name = current_dir_path
-        .file_name()
-        .or_else(|| path.file_name())
-        .unwrap_or_else(|| path.as_os_str())
-        .to_string_lossy()
-        .green()
-        .to_string();
-
-    // Tree directories compare logic
-    if let Some(compare_path) = compare_path {
-        // Compare two directories
-        compare_directories(&path, &compare_path);
+    let exclude = matches.get_many::<String>("exclude");
+    let size_min = matches.get_one::<u64>("size_min");
+    let size_max = matches.get_one::<u64>("size_max");
+
+    // Scan with the same filters for the tree view and for both sides of a comparison
+    let mut builder = TreeGen::builder(path)
+        .depth(depth_int)
+        .ignore_hidden(*ignore_hidden)
+        .time_field(time_field)
+        .sort(sort);
+    if let Some(file_extension) = file_extension {
+        builder = builder.extension(file_extension);
+    }
+    if let Some(size_min) = size_min {
+        builder = builder.size_min(*size_min);
+    }
+    if let Some(size_max) = size_max {
+        builder = builder.size_max(*size_max);
+    }
+    if let Some(date_filter) = date_filter {
+        builder = builder.date_filter(date_filter);
+    }
+    if let Some(include) = include {
+        builder = builder.include(include);
+    }
+    for exclude in exclude.into_iter().flatten() {
+        builder = builder.exclude(exclude);
+    }
+    let render_options = RenderOptions {
+        branch_style: match branch_style.map(String::as_str) {
+            Some("ascii") => BranchStyle::Ascii,
+            _ => BranchStyle::Unicode,
+        },
+        show_size: *show_size,
+        icons: *icons,
+        preview_lines: preview_lines.copied(),
+        // Only colorize output shown in the terminal
+        color: output_file.is_none(),
+        title: title.cloned(),
+        markdown_links: *markdown_links,
+        human_readable: *human_readable,
+        columns,
+        time_format: TimeFormat::new(time_format, TimeZone::local()),
+        time_field,
+    };
+
+    let mut output = open_output(output_file)?;
+    let mut exit_code = 0;
+    if let (Some(compare_path), Some(base_path)) = (compare_path, base_path) {
+        // Compare our and their directories against the base they come from
+        let options = builder.options()?;
+        let renderer = get_merge_renderer(compare_format, &render_options).ok_or_else(|| {
+            TreeGenError::Compare(format!(
+                "the {} format cannot show a three-way comparison",
+                compare_format
+            ))
+        })?;
+        let merge = compare_directories_three_way(
+            base_path,
+            path,
+            compare_path,
+            &options,
+            &compare_options,
+        )?;
+        renderer.render(&merge, &mut output)?;
+
+        let summary = merge.summary();
+        exit_code = match (summary.errors > 0, summary.conflicts > 0) {
+            (true, _) => 2,
+            (false, true) => 1,
+            (false, false) => 0,
+        };
+    } else if let Some(compare_path) = compare_path {
+        // Compare two directories or JSON snapshots, or a directory and a saved text tree
+        let options = builder.options()?;
+        let left_snapshot = read_compare_snapshot(path)?;
+        let right_snapshot = read_compare_snapshot(compare_path)?;
+        let diff = match compare_path.is_file() && right_snapshot.is_none() {
+            true => compare_with_text_tree(path, compare_path, &options, &compare_options)?,
+            false => compare_directories(
+                compare_source(left_snapshot.as_ref(), path),
+                compare_source(right_snapshot.as_ref(), compare_path),
+                &options,
+                &compare_options,
+            )?,
+        };
+        // The format is validated by clap against the registered renderers
+        let renderer = get_diff_renderer(compare_format, &render_options).unwrap();
+        renderer.render(&diff, &mut output)?;
+
+        let summary = diff.summary();
+        exit_code = match (summary.errors > 0, summary.has_differences()) {
+            (true, _) => 2,
+            (false, true) => 1,
+            (false, false) => 0,
+        };
     } else {
-        // Below is the tree generations functions
-        println!("{}", root_dir_name);
-        if *json {
-            let json_tree = generate_json_tree(
-                path,
-                ignore_hidden.to_owned(),
-                &root_dir_name,
-                file_extension,
-                size_min,
-                size_max,
-                date_filter,
-                include,
-                exclude,
-            );
-            let json_tree_output =
-                serde_json::to_string_pretty(&json_tree).expect("Failed to serialize the JSON");
-
-            if let Some(output_file) = output_file {
-                fs::write(output_file, json_tree_output).expect("Failed to write to file");
-                println!("JSON output has been written to {}", output_file);
-            } else {
-                println!("{}", json_tree_output)
+        // Render the tree with the selected output format
+        // The format is validated by clap against the registered renderers
+        let renderer = get_renderer(format, &render_options).unwrap();
+        let builder = builder.directory_sizes(renderer.directory_sizes());
+        match renderer.streaming() {
+            // Streaming formats write entries while walking instead of scanning the tree first
+            Some(stream_renderer) => builder.stream(stream_renderer, &mut output)?,
+            None => builder.build()?.render(renderer.as_ref(), &mut output)?,
+        }
+    }
+    output.flush()?;
+
+    if let Some(output_file) = output_file {
+        println!("Output has been written to {}", output_file);
+    }
+
+    Ok(exit_code)
+}
+
+/// Read a compared path as a JSON snapshot when it is a file holding one.
+fn read_compare_snapshot(path: &Path) -> Result<Option<TreeNode>> {
+    let is_snapshot = path.is_file() && starts_json_object(path).unwrap_or(false);
+    match is_snapshot {
+        true => TreeNode::read_snapshot(path).map(Some),
+        false => Ok(None),
+    }
+}
+
+/// Check whether a file starts with `{` after any whitespace, reading no further.
+fn starts_json_object(path: &Path) -> io::Result<bool> {
+    for byte in BufReader::new(File::open(path)?).bytes() {
+        let byte = byte?;
+        if !byte.is_ascii_whitespace() {
+            return Ok(byte == b'{');
+        }
+    }
+    Ok(false)
+}
+
+fn compare_source<'a>(snapshot: Option<&'a TreeNode>, path: &'a Path) -> TreeSource<'a> {
+    match snapshot {
+        Some(snapshot) => TreeSource::Snapshot(snapshot, path),
+        None => TreeSource::Directory(path),
+    }
+}
+
+fn run_restore(matches: &ArgMatches) -> Result<()> {
+    let snapshot_path = Path::new(matches.get_one::<String>("snapshot").unwrap());
+    let dest = Path::new(matches.get_one::<String>("dest").unwrap());
+    let restore_options = RestoreOptions {
+        fill: *matches.get_one::<bool>("fill").unwrap(),
+    };
+
+    let snapshot = TreeNode::read_snapshot(snapshot_path)?;
+    let summary = restore(&snapshot, dest, &restore_options)?;
+
+    println!(
+        "Restored {} directories, {} files and {} symlinks into {}",
+        summary.directories,
+        summary.files,
+        summary.symlinks,
+        dest.display()
+    );
+    if summary.skipped > 0 {
+        println!(
+            "Skipped {} fifos, sockets and devices that cannot be recreated",
+            summary.skipped
+        );
+    }
+    Ok(())
+}
+
+fn run_scaffold(matches: &ArgMatches) -> Result<()> {
+    let input = Path::new(matches.get_one::<String>("input").unwrap());
+    let dest = Path::new(matches.get_one::<String>("dest").unwrap());
+    let dry_run = *matches.get_one::<bool>("dry_run").unwrap();
+
+    let (text, source) = match input == Path::new("-") {
+        true => {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text)?;
+            (text, Path::new("<stdin>"))
+        }
+        false => {
+            let text = fs::read_to_string(input).map_err(|source| TreeGenError::Path {
+                path: input.to_path_buf(),
+                source,
+            })?;
+            (text, input)
+        }
+    };
+    let tree = parse_tree(&text, source)?;
+    let entries = scaffold::plan(&tree, dest)?;
+
+    if dry_run {
+        for entry in &entries {
+            let action = match (entry.exists, entry.kind) {
+                (true, _) => "exists",
+                (false, NodeKind::Directory) => "mkdir ",
+                (false, NodeKind::Symlink) => "ln -s ",
+                (false, _) => "touch ",
+            };
+            match &entry.link_target {
+                Some(target) => println!(
+                    "{} {} -> {}",
+                    action,
+                    entry.path.display(),
+                    target.display()
+                ),
+                None => println!("{} {}", action, entry.path.display()),
             }
-        } else {
-            // Start the recursive tree generation for subdirectories
-            generate_tree(
-                path,
-                "",
-                file_extension,
-                output_file,
-                1,
-                max_depth,
-                ignore_hidden.to_owned(),
-                show_size.to_owned(),
-                branch_style,
-                preview_lines,
-                date_filter,
-                size_min,
-                size_max,
-                include,
-                exclude,
-                icons.to_owned(),
-            );
         }
+        return Ok(());
     }
+
+    scaffold::create(dest, &entries)?;
+    let created = |kind: NodeKind| {
+        entries
+            .iter()
+            .filter(|entry| !entry.exists && entry.kind == kind)
+            .count()
+    };
+    println!(
+        "Created {} directories, {} files and {} symlinks in {}",
+        created(NodeKind::Directory),
+        created(NodeKind::File),
+        created(NodeKind::Symlink),
+        dest.display()
+    );
+    Ok(())
 }